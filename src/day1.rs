@@ -1,49 +1,298 @@
 use crate::Part;
+use std::collections::BTreeMap;
+use std::io::{self, Read};
 
 pub fn run(input: &str, part: Part) -> String {
-    let input = parse_input(input);
-    format!(
-        "{}",
-        match part {
-            Part::One => lift(&input),
-            Part::Two => find_basement(&input) as i32,
+    let directions = input.as_bytes();
+    match part {
+        Part::One => format!("{}", lift(directions)),
+        Part::Two => match find_basement(directions) {
+            Some(pos) => format!("{}", pos),
+            None => "never reached basement".to_string(),
+        },
+    }
+}
+
+// Everything that happened to the lift while following a list of directions.  Positions are
+// 1-based counts of directions followed; position 0 is the starting point on floor 0.  Without the
+// per-floor counts the size is fixed, however long the input; with them it grows with the range of
+// floors visited, which can be as much as the length of the input.
+#[derive(Debug, PartialEq)]
+struct Timeline {
+    final_floor: i64,
+    instructions: u64,
+    // how many times the lift went from floor 0 down to floor -1, and the first and last
+    // positions it did
+    basement_entries: u64,
+    first_basement: Option<u64>,
+    last_basement: Option<u64>,
+    // highest and lowest floors, with the first position each was reached
+    max_floor: (i64, u64),
+    min_floor: (i64, u64),
+    // if asked for, the number of positions (including the start) spent on each floor; floors 0
+    // and up, then floors -1 and down.  The lift only moves one floor at a time so there are no
+    // gaps.
+    floor_counts: Option<(Vec<u64>, Vec<u64>)>,
+}
+
+impl Timeline {
+    fn new(count_floors: bool) -> Self {
+        Self {
+            final_floor: 0,
+            instructions: 0,
+            basement_entries: 0,
+            first_basement: None,
+            last_basement: None,
+            max_floor: (0, 0),
+            min_floor: (0, 0),
+            floor_counts: count_floors.then(|| (vec![1], vec![])),
+        }
+    }
+
+    // positions spent on each floor, if they were counted
+    #[allow(dead_code)]
+    fn histogram(&self) -> Option<BTreeMap<i64, u64>> {
+        let (above, below) = self.floor_counts.as_ref()?;
+        let above = above.iter().enumerate().map(|(i, &n)| (i as i64, n));
+        let below = below.iter().enumerate().map(|(i, &n)| (-1 - i as i64, n));
+        Some(above.chain(below).collect())
+    }
+
+    fn step(&mut self, up: bool) {
+        self.instructions += 1;
+        if up {
+            self.final_floor += 1;
+            if self.final_floor > self.max_floor.0 {
+                self.max_floor = (self.final_floor, self.instructions);
+            }
+        } else {
+            self.final_floor -= 1;
+            if self.final_floor == -1 {
+                self.basement_entries += 1;
+                self.first_basement.get_or_insert(self.instructions);
+                self.last_basement = Some(self.instructions);
+            }
+            if self.final_floor < self.min_floor.0 {
+                self.min_floor = (self.final_floor, self.instructions);
+            }
+        }
+        let Some((above, below)) = &mut self.floor_counts else {
+            return;
+        };
+        let (counts, i) = if self.final_floor >= 0 {
+            (above, self.final_floor as usize)
+        } else {
+            (below, (-1 - self.final_floor) as usize)
+        };
+        if i == counts.len() {
+            counts.push(0);
         }
-    )
+        counts[i] += 1;
+    }
+}
+
+// Follows directions from any reader a buffer at a time, so the whole input never needs to be in
+// memory.  Anything other than ( or ) (such as a trailing newline) is ignored.  count_floors asks
+// for the histogram, which needs memory for every floor visited.
+#[allow(dead_code)]
+fn analyse(mut reader: impl Read, count_floors: bool) -> io::Result<Timeline> {
+    let mut timeline = Timeline::new(count_floors);
+    let mut buf = [0; 64 * 1024];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(timeline),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for &b in &buf[..n] {
+            match b {
+                b'(' => timeline.step(true),
+                b')' => timeline.step(false),
+                _ => {}
+            }
+        }
+    }
 }
 
-fn parse_input(input: &str) -> Vec<char> {
-    input.lines().next().unwrap().chars().collect()
+// Fast paths over a byte slice for huge inputs.  Bytes are counted 8 at a time by treating each
+// chunk as a u64 (SWAR).  Like analyse, anything other than ( or ) is ignored and positions
+// count directions only.
+
+const ONES: u64 = 0x0101_0101_0101_0101;
+const LOW7: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+const CHUNK: usize = 64;
+
+// number of bytes in word equal to b
+fn count_byte(word: u64, b: u8) -> u32 {
+    // matching bytes become zero
+    let x = word ^ (ONES * b as u64);
+    // high bit of each byte is set iff that byte is zero (no carries between bytes)
+    (!(((x & LOW7) + LOW7) | x | LOW7)).count_ones()
+}
+
+// (number of '(', number of ')') in a slice
+fn count_parens(directions: &[u8]) -> (u64, u64) {
+    let mut words = directions.chunks_exact(8);
+    let (mut up, mut down) = (0, 0);
+    for w in words.by_ref() {
+        let w = u64::from_le_bytes(w.try_into().unwrap());
+        up += count_byte(w, b'(') as u64;
+        down += count_byte(w, b')') as u64;
+    }
+    for &b in words.remainder() {
+        match b {
+            b'(' => up += 1,
+            b')' => down += 1,
+            _ => {}
+        }
+    }
+    (up, down)
 }
 
-fn lift(directions: &[char]) -> i32 {
-    directions
-        .iter()
-        .fold(0, |acc, &c| acc + if c == '(' { 1 } else { -1 })
+fn lift(directions: &[u8]) -> i64 {
+    let (up, down) = count_parens(directions);
+    up as i64 - down as i64
 }
 
-fn find_basement(directions: &[char]) -> usize {
-    let mut level = 0;
-    for (i, &d) in directions.iter().enumerate() {
-        level += if d == '(' { 1 } else { -1 };
-        if level == -1 {
-            return i + 1;
+// Position of the first direction that enters the basement.  A chunk is only scanned byte by byte
+// if it has enough )s to take the lift from its current floor down to -1; otherwise the floor and
+// position are just advanced by the chunk's totals.
+fn find_basement(directions: &[u8]) -> Option<u64> {
+    let mut floor = 0i64;
+    let mut position = 0u64;
+    for chunk in directions.chunks(CHUNK) {
+        let (up, down) = count_parens(chunk);
+        if floor - (down as i64) >= 0 {
+            floor += up as i64 - down as i64;
+            position += up + down;
+            continue;
+        }
+        for &b in chunk {
+            match b {
+                b'(' => floor += 1,
+                b')' => floor -= 1,
+                _ => continue,
+            }
+            position += 1;
+            if floor == -1 {
+                return Some(position);
+            }
         }
     }
-    panic!("never reached basement");
+    None
 }
 
 #[test]
 fn test() {
-    assert_eq!(0, lift(&parse_input("(())\n")));
-    assert_eq!(0, lift(&parse_input("()()\n")));
-    assert_eq!(3, lift(&parse_input("(((\n")));
-    assert_eq!(3, lift(&parse_input("(()(()(\n")));
-    assert_eq!(3, lift(&parse_input("))(((((\n")));
-    assert_eq!(-1, lift(&parse_input("())\n")));
-    assert_eq!(-1, lift(&parse_input("))(\n")));
-    assert_eq!(-3, lift(&parse_input(")))\n")));
-    assert_eq!(-3, lift(&parse_input(")())())\n")));
-
-    assert_eq!(1, find_basement(&parse_input(")\n")));
-    assert_eq!(5, find_basement(&parse_input("()())\n")));
+    let lift = |s: &str| {
+        let floor = analyse(s.as_bytes(), false).unwrap().final_floor;
+        assert_eq!(floor, lift(s.as_bytes()));
+        floor
+    };
+    assert_eq!(0, lift("(())\n"));
+    assert_eq!(0, lift("()()\n"));
+    assert_eq!(3, lift("(((\n"));
+    assert_eq!(3, lift("(()(()(\n"));
+    assert_eq!(3, lift("))(((((\n"));
+    assert_eq!(-1, lift("())\n"));
+    assert_eq!(-1, lift("))(\n"));
+    assert_eq!(-3, lift(")))\n"));
+    assert_eq!(-3, lift(")())())\n"));
+
+    let find_basement = |s: &str| {
+        let pos = analyse(s.as_bytes(), false).unwrap().first_basement;
+        assert_eq!(pos, find_basement(s.as_bytes()));
+        pos
+    };
+    assert_eq!(Some(1), find_basement(")\n"));
+    assert_eq!(Some(5), find_basement("()())\n"));
+    assert_eq!(None, find_basement("(()\n"));
+
+    assert_eq!(
+        Timeline {
+            final_floor: -1,
+            instructions: 7,
+            basement_entries: 2,
+            first_basement: Some(1),
+            last_basement: Some(5),
+            max_floor: (1, 3),
+            min_floor: (-2, 6),
+            floor_counts: Some((vec![3, 1], vec![3, 1])),
+        },
+        analyse(")(()\n))(".as_bytes(), true).unwrap()
+    );
+    assert_eq!(
+        Some(BTreeMap::from([(-2, 1), (-1, 3), (0, 3), (1, 1)])),
+        analyse(")(()\n))(".as_bytes(), true).unwrap().histogram()
+    );
+    assert_eq!(
+        None,
+        analyse(")(()\n))(".as_bytes(), false).unwrap().histogram()
+    );
+}
+
+// Random walks starting with a run of (s, so the basement is usually reached (if at all) well into
+// the input and the chunk skipping is exercised.  down is the chance per thousand of each
+// direction being ).  Includes newlines to check they're ignored.
+#[cfg(test)]
+fn random_directions(len: usize, climb: usize, down: u64, seed: u64) -> Vec<u8> {
+    use crate::rng::Rng;
+
+    let mut rng = Rng::new(seed);
+    (0..len)
+        .map(|i| match rng.below(1000) as u64 {
+            _ if i < climb => b'(',
+            0 => b'\n',
+            n if n <= down => b')',
+            _ => b'(',
+        })
+        .collect()
+}
+
+#[test]
+fn test_fast_path() {
+    for (len, seed) in [(7, 1), (100, 2), (5000, 3), (100_000, 4), (100_000, 5)] {
+        let mut directions = random_directions(len, len / 100, 480, seed);
+        // and a version guaranteed to reach the basement near the end
+        let mut sunk = directions.clone();
+        sunk.extend(std::iter::repeat_n(b')', len + 1));
+        for d in [&mut directions, &mut sunk] {
+            let timeline = analyse(&d[..], false).unwrap();
+            assert_eq!(timeline.final_floor, lift(d));
+            assert_eq!(timeline.first_basement, find_basement(d));
+        }
+    }
+}
+
+// cargo test --release bench_1gb -- --ignored --nocapture
+#[test]
+#[ignore]
+fn bench_1gb() {
+    use std::time::Instant;
+
+    let directions = random_directions(1 << 30, 1000, 500, 42);
+
+    let t0 = Instant::now();
+    let timeline = analyse(&directions[..], false).unwrap();
+    println!("analyse:       {:.3}s", t0.elapsed().as_secs_f64());
+
+    let t0 = Instant::now();
+    assert_eq!(timeline.final_floor, lift(&directions));
+    println!("lift:          {:.3}s", t0.elapsed().as_secs_f64());
+
+    let t0 = Instant::now();
+    assert_eq!(timeline.first_basement, find_basement(&directions));
+    println!("find_basement: {:.3}s", t0.elapsed().as_secs_f64());
+
+    // worst case for find_basement: straight up then down, only reaching the basement at the end
+    let mut directions = vec![b'('; 1 << 29];
+    directions.resize(1 << 30, b')');
+    directions.push(b')');
+    let t0 = Instant::now();
+    assert_eq!(Some(1 << 30 | 1), find_basement(&directions));
+    println!(
+        "find_basement: {:.3}s (worst case)",
+        t0.elapsed().as_secs_f64()
+    );
 }
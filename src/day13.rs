@@ -1,7 +1,6 @@
+use crate::parse::{self, ParseError, Parser};
 use crate::Part;
 use itertools::Itertools;
-use lazy_static::lazy_static;
-use regex::Regex;
 use std::collections::HashMap;
 use std::fmt;
 
@@ -69,40 +68,37 @@ impl fmt::Debug for Diners {
     }
 }
 
+// Alice would gain 54 happiness units by sitting next to Bob.
+fn parse_line(p: &mut Parser) -> Result<(String, i32, String), ParseError> {
+    let p1 = p.ident()?.to_string();
+    p.literal(" would ")?;
+    let sign = p.keyword(&[("gain", 1), ("lose", -1)])?;
+    p.literal(" ")?;
+    let h: i32 = p.integer()?;
+    p.literal(" happiness units by sitting next to ")?;
+    let p2 = p.ident()?.to_string();
+    p.literal(".")?;
+    Ok((p1, sign * h, p2))
+}
+
 fn parse_input(input: &str) -> Diners {
     let mut names: Vec<String> = vec![];
     let mut happiness = HashMap::new();
 
-    lazy_static! {
-        static ref RE: Regex = Regex::new(
-            r"^(\w+) would (gain|lose) (\d+) happiness units by sitting next to (\w+).$"
-        )
-        .unwrap();
-    }
-
-    for line in input.lines() {
-        let caps = RE.captures(line).unwrap();
-        let p1 = if let Some(pos) = names.iter().position(|name| name == &caps[1]) {
+    for (name1, h, name2) in parse::lines(input, parse_line).unwrap() {
+        let p1 = if let Some(pos) = names.iter().position(|name| name == &name1) {
             pos
         } else {
-            names.push(caps[1].to_string());
+            names.push(name1);
             names.len() - 1
         };
-        let p2 = if let Some(pos) = names.iter().position(|name| name == &caps[4]) {
+        let p2 = if let Some(pos) = names.iter().position(|name| name == &name2) {
             pos
         } else {
-            names.push(caps[4].to_string());
+            names.push(name2);
             names.len() - 1
         };
-        let h = caps[3].parse::<i32>().unwrap();
-        happiness.insert(
-            (p1, p2),
-            match &caps[2] {
-                "gain" => h,
-                "lose" => -h,
-                _ => panic!(),
-            },
-        );
+        happiness.insert((p1, p2), h);
     }
 
     Diners { names, happiness }
@@ -1,6 +1,5 @@
+use crate::parse::{self, ParseError, Parser};
 use crate::Part;
-use lazy_static::lazy_static;
-use regex::Regex;
 
 pub fn run(input: &str, part: Part) -> String {
     let reindeer = parse_input(input);
@@ -93,24 +92,28 @@ fn simulate(reindeer: &[Reindeer], seconds: u32) -> Vec<State> {
     state
 }
 
-impl From<&str> for Reindeer {
-    fn from(s: &str) -> Self {
-        lazy_static! {
-            static ref RE: Regex =
-                Regex::new(r"^(\w+) can fly (\d+) km/s for (\d+) seconds, but then must rest for (\d+) seconds\.$").unwrap();
-        }
-        let m = RE.captures(s).unwrap();
-        Self {
-            _name: m[1].to_string(),
-            speed: m[2].parse().unwrap(),
-            flight_period: m[3].parse().unwrap(),
-            rest_period: m[4].parse().unwrap(),
-        }
+// Comet can fly 14 km/s for 10 seconds, but then must rest for 127 seconds.
+impl Reindeer {
+    fn parse(p: &mut Parser) -> Result<Self, ParseError> {
+        let name = p.ident()?.to_string();
+        p.literal(" can fly ")?;
+        let speed = p.integer()?;
+        p.literal(" km/s for ")?;
+        let flight_period = p.integer()?;
+        p.literal(" seconds, but then must rest for ")?;
+        let rest_period = p.integer()?;
+        p.literal(" seconds.")?;
+        Ok(Self {
+            _name: name,
+            speed,
+            flight_period,
+            rest_period,
+        })
     }
 }
 
 fn parse_input(input: &str) -> Vec<Reindeer> {
-    input.lines().map(Reindeer::from).collect()
+    parse::lines(input, Reindeer::parse).unwrap()
 }
 
 #[test]
@@ -1,3 +1,4 @@
+use crate::parse::{self, ParseError, Parser};
 use crate::Part;
 
 pub fn run(input: &str, part: Part) -> String {
@@ -62,29 +63,20 @@ struct Aunt {
 // example input:
 // Sue 1: children: 1, cars: 8, vizslas: 7
 // Sue 2: akitas: 10, perfumes: 10, children: 5
+fn parse_aunt(p: &mut Parser) -> Result<Aunt, ParseError> {
+    p.literal("Sue ")?;
+    let number = p.integer()?;
+    p.literal(": ")?;
+    let items = p.separated(", ", |p| {
+        let item = p.ident()?.to_string();
+        p.literal(": ")?;
+        Ok((item, p.integer()?))
+    })?;
+    Ok(Aunt { number, items })
+}
+
 fn parse_input(input: &str) -> Vec<Aunt> {
-    let trailing_chars = [',', ':'];
-    input
-        .lines()
-        .map(|line| {
-            let mut number = 0;
-            let mut items = vec![];
-            let mut words = line.split(' ');
-            while let Some(word) = words.next() {
-                let value: u32 = words
-                    .next()
-                    .unwrap()
-                    .trim_end_matches(&trailing_chars)
-                    .parse()
-                    .unwrap();
-                match word.trim_end_matches(&trailing_chars) {
-                    "Sue" => number = value,
-                    _ => items.push((word.trim_end_matches(&trailing_chars).to_string(), value)),
-                }
-            }
-            Aunt { number, items }
-        })
-        .collect()
+    parse::lines(input, parse_aunt).unwrap()
 }
 
 #[test]
@@ -1,3 +1,4 @@
+use crate::search::{self, SearchProblem};
 use crate::Part;
 use std::collections::HashSet;
 
@@ -12,7 +13,7 @@ pub fn run(input: &str, part: Part) -> String {
     )
 }
 
-fn part1(replacements: &Vec<Replacement>, molecule: &str) -> u32 {
+fn part1(replacements: &[Replacement], molecule: &str) -> u32 {
     let mut molecules = HashSet::new();
     for replacement in replacements {
         for (pos, _) in molecule.match_indices(&replacement.from) {
@@ -28,37 +29,57 @@ fn part1(replacements: &Vec<Replacement>, molecule: &str) -> u32 {
     molecules.len() as u32
 }
 
-fn part2(replacements: &Vec<Replacement>, molecule: &str) -> u32 {
-    let mut answer = None;
-    part2_dfs(replacements, molecule, 0, &mut answer);
-    answer.unwrap()
+fn part2(replacements: &[Replacement], molecule: &str) -> u32 {
+    let reduction = Reduction {
+        replacements,
+        molecule,
+    };
+    search::dfs(&reduction).unwrap().cost
 }
 
-// this happens to finds my correct answer very quickly but doesn't guarantee the correct answer
-// for all inputs because it is not exhaustive.
-// TODO: Need something cleverer.
-fn part2_dfs(
-    replacements: &Vec<Replacement>,
-    molecule: &str,
-    depth: u32,
-    answer: &mut Option<u32>,
-) {
-    if molecule == "e" {
-        *answer = Some(depth);
-    } else {
-        for replacement in replacements {
+// Works backwards from the medicine molecule, undoing replacements until only "e" is left.
+//
+// A depth-first search finds my answer very quickly, where anything exhaustive drowns in the
+// number of orders the same replacements can be undone in.  It doesn't need to find the shortest
+// route: every replacement in the puzzle's grammar is one of X => XX, X => XRnXAr,
+// X => XRnXYXAr or X => XRnXYXYXAr, so the number of steps to build a molecule is fixed by its
+// element counts and any route back to "e" is the shortest.
+struct Reduction<'a> {
+    replacements: &'a [Replacement],
+    molecule: &'a str,
+}
+
+impl SearchProblem for Reduction<'_> {
+    type State = String;
+    // (index of replacement, position in molecule)
+    type Move = (usize, usize);
+
+    fn start(&self) -> String {
+        self.molecule.to_string()
+    }
+
+    fn is_goal(&self, molecule: &String) -> bool {
+        molecule == "e"
+    }
+
+    fn successors(&self, molecule: &String) -> Vec<((usize, usize), String, u32)> {
+        let mut moves = vec![];
+        for (i, replacement) in self.replacements.iter().enumerate() {
+            // "e" can only be the whole molecule
+            if replacement.from == "e" && replacement.to != *molecule {
+                continue;
+            }
             for (pos, _) in molecule.match_indices(&replacement.to) {
-                if answer.is_none() {
-                    let new_molecule = format!(
-                        "{}{}{}",
-                        &molecule[..pos],
-                        replacement.from,
-                        &molecule[(pos + replacement.to.len())..]
-                    );
-                    part2_dfs(replacements, &new_molecule, depth + 1, answer);
-                }
+                let new_molecule = format!(
+                    "{}{}{}",
+                    &molecule[..pos],
+                    replacement.from,
+                    &molecule[(pos + replacement.to.len())..]
+                );
+                moves.push(((i, pos), new_molecule, 1));
             }
         }
+        moves
     }
 }
 
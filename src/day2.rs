@@ -1,71 +1,389 @@
+use crate::parse::{self, ParseError, Parser};
 use crate::Part;
+use std::fmt::Write;
 
 pub fn run(input: &str, part: Part) -> String {
-    let input = parse_input(input);
+    let input = parse_input(input).unwrap();
+    let totals = total_materials(&input).expect("totals overflowed");
     format!(
         "{}",
         match part {
-            Part::One => total_paper(&input),
-            Part::Two => total_ribbon(&input),
+            Part::One => totals.paper(),
+            Part::Two => totals.ribbon(),
         }
     )
 }
 
 struct Present {
-    l: u32,
-    w: u32,
-    h: u32,
+    l: u64,
+    w: u64,
+    h: u64,
 }
 
-impl From<&str> for Present {
-    fn from(s: &str) -> Self {
-        let dims: Vec<u32> = s.split('x').map(|n| n.parse().unwrap()).collect();
-        Self {
+// 2x3x4
+impl Present {
+    fn parse(p: &mut Parser) -> Result<Self, ParseError> {
+        let dims = p.separated("x", |p| {
+            let start = p.position();
+            let n: u64 = p.integer()?;
+            if n == 0 {
+                return Err(p.error_at(start, "dimensions must be at least 1"));
+            }
+            Ok(n)
+        })?;
+        if dims.len() != 3 {
+            return Err(p.error(format!("expected 3 dimensions, found {}", dims.len())));
+        }
+        Ok(Self {
             l: dims[0],
             w: dims[1],
             h: dims[2],
+        })
+    }
+
+    // None if any quantity doesn't fit in a u64
+    fn materials(&self) -> Option<Materials> {
+        let sides = [
+            self.l.checked_mul(self.w)?,
+            self.w.checked_mul(self.h)?,
+            self.h.checked_mul(self.l)?,
+        ];
+        let perimeters = [
+            self.l.checked_add(self.w)?,
+            self.l.checked_add(self.h)?,
+            self.w.checked_add(self.h)?,
+        ];
+        Materials {
+            surface: sides
+                .iter()
+                .try_fold(0u64, |acc, s| acc.checked_add(s.checked_mul(2)?))?,
+            slack: *sides.iter().min().unwrap(),
+            wrap: perimeters.iter().min().unwrap().checked_mul(2)?,
+            bow: sides[0].checked_mul(self.h)?,
         }
+        .checked()
     }
 }
 
-impl Present {
-    fn wrapping_paper(&self) -> u32 {
-        let sides: Vec<u32> = vec![self.l * self.w, self.w * self.h, self.h * self.l];
-        sides.iter().map(|s| s * 2).sum::<u32>() + sides.iter().min().unwrap()
+// Bill of materials for one present, or a total for several
+#[derive(Debug, Default, PartialEq)]
+struct Materials {
+    surface: u64, // paper to cover every side
+    slack: u64,   // extra paper: the area of the smallest side
+    wrap: u64,    // ribbon to wrap around the smallest perimeter
+    bow: u64,     // ribbon for the bow: the volume
+}
+
+impl Materials {
+    fn paper(&self) -> u64 {
+        self.surface + self.slack
+    }
+
+    fn ribbon(&self) -> u64 {
+        self.wrap + self.bow
+    }
+
+    fn checked_add(&self, other: &Materials) -> Option<Materials> {
+        Materials {
+            surface: self.surface.checked_add(other.surface)?,
+            slack: self.slack.checked_add(other.slack)?,
+            wrap: self.wrap.checked_add(other.wrap)?,
+            bow: self.bow.checked_add(other.bow)?,
+        }
+        .checked()
     }
 
-    fn smallest_perimeter(&self) -> u32 {
-        2 * vec![self.l + self.w, self.l + self.h, self.w + self.h]
-            .iter()
-            .min()
-            .unwrap()
+    // None unless paper() and ribbon() fit in a u64 too
+    fn checked(self) -> Option<Materials> {
+        self.surface.checked_add(self.slack)?;
+        self.wrap.checked_add(self.bow)?;
+        Some(self)
     }
+}
+
+fn parse_input(input: &str) -> Result<Vec<Present>, ParseError> {
+    parse::lines(input, Present::parse)
+}
+
+fn total_materials(presents: &[Present]) -> Option<Materials> {
+    presents.iter().try_fold(Materials::default(), |acc, p| {
+        acc.checked_add(&p.materials()?)
+    })
+}
+
+// One row per present then a row of totals.  Lengths are in feet, areas in square feet.
+#[allow(dead_code)]
+fn bill_of_materials_csv(presents: &[Present]) -> Option<String> {
+    let mut csv = String::from("present,l,w,h,surface,slack,paper,wrap,bow,ribbon\n");
+    let mut row = |name: &str, dims: [String; 3], m: &Materials| {
+        writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{},{}",
+            name,
+            dims[0],
+            dims[1],
+            dims[2],
+            m.surface,
+            m.slack,
+            m.paper(),
+            m.wrap,
+            m.bow,
+            m.ribbon()
+        )
+        .unwrap();
+    };
+    for (i, p) in presents.iter().enumerate() {
+        let dims = [p.l, p.w, p.h].map(|d| d.to_string());
+        row(&(i + 1).to_string(), dims, &p.materials()?);
+    }
+    let dims = [String::new(), String::new(), String::new()];
+    row("total", dims, &total_materials(presents)?);
+    Some(csv)
+}
+
+// Cutting paper from a roll of fixed width instead of infinite sheets.  Each present needs its six
+// sides plus a slack piece the size of its smallest side; every piece is packed onto the roll
+// (turned sideways if that fits better) with a skyline bottom-left heuristic.
+
+#[derive(Debug, PartialEq)]
+struct Placement {
+    present: usize, // index into the list of presents
+    x: u64,         // across the roll
+    y: u64,         // along the roll
+    width: u64,
+    length: u64,
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+struct RollLayout {
+    width: u64,
+    length: u64, // amount of roll used
+    placements: Vec<Placement>,
+}
 
-    fn volume(&self) -> u32 {
-        self.l * self.w * self.h
+#[allow(dead_code)]
+impl RollLayout {
+    // the shortest the roll could be if no paper was wasted
+    fn theoretical_length(&self, total_paper: u64) -> u64 {
+        total_paper.div_ceil(self.width)
     }
 
-    fn ribbon(&self) -> u32 {
-        self.smallest_perimeter() + self.volume()
+    fn report(&self, total_paper: u64) -> String {
+        let area = self.width * self.length;
+        format!(
+            "{} feet of {} foot roll ({} square feet) for {} square feet of paper: {:.1}% used, \
+             at least {} feet needed",
+            self.length,
+            self.width,
+            area,
+            total_paper,
+            if area == 0 {
+                100.0
+            } else {
+                total_paper as f64 * 100.0 / area as f64
+            },
+            self.theoretical_length(total_paper)
+        )
     }
 }
 
-fn parse_input(input: &str) -> Vec<Present> {
-    input.lines().map(Present::from).collect()
+// A horizontal stretch of the skyline: everything at y below height is used
+struct Segment {
+    x: u64,
+    width: u64,
+    height: u64,
 }
 
-fn total_paper(presents: &[Present]) -> u32 {
-    presents.iter().map(|p| p.wrapping_paper()).sum()
+// None if a piece is too big for the roll whichever way round it goes
+#[allow(dead_code)]
+fn layout_on_roll(presents: &[Present], width: u64) -> Option<RollLayout> {
+    let mut pieces = vec![];
+    for (i, p) in presents.iter().enumerate() {
+        let mut sides = [(p.l, p.w), (p.w, p.h), (p.h, p.l)];
+        sides.sort_by_key(|(a, b)| a * b);
+        for side in [
+            sides[0], sides[0], sides[0], sides[1], sides[1], sides[2], sides[2],
+        ] {
+            // long edge along the roll unless it's too wide that way
+            let (short, long) = (side.0.min(side.1), side.0.max(side.1));
+            if short > width {
+                return None;
+            }
+            pieces.push((i, short, long));
+        }
+    }
+    // biggest pieces first pack best
+    pieces.sort_by_key(|&(_, w, l)| std::cmp::Reverse((l, w)));
+
+    let mut skyline = vec![Segment {
+        x: 0,
+        width,
+        height: 0,
+    }];
+    let mut placements = vec![];
+    for (present, w, l) in pieces {
+        let mut best: Option<(u64, u64, u64, u64)> = None; // (top, x, width, length)
+        for (pw, pl) in [(w, l), (l, w)] {
+            if pw > width {
+                continue;
+            }
+            for start in &skyline {
+                if start.x + pw > width {
+                    break;
+                }
+                let y = skyline
+                    .iter()
+                    .filter(|s| s.x < start.x + pw && s.x + s.width > start.x)
+                    .map(|s| s.height)
+                    .max()
+                    .unwrap();
+                let candidate = (y + pl, start.x, pw, pl);
+                if best.is_none_or(|b| (candidate.0, candidate.1) < (b.0, b.1)) {
+                    best = Some(candidate);
+                }
+            }
+        }
+        let (top, x, pw, pl) = best.unwrap();
+        placements.push(Placement {
+            present,
+            x,
+            y: top - pl,
+            width: pw,
+            length: pl,
+        });
+        raise_skyline(&mut skyline, x, pw, top);
+    }
+
+    Some(RollLayout {
+        width,
+        length: skyline.iter().map(|s| s.height).max().unwrap(),
+        placements,
+    })
 }
 
-fn total_ribbon(presents: &[Present]) -> u32 {
-    presents.iter().map(|p| p.ribbon()).sum()
+// replace the skyline between x and x + width with a segment at height
+fn raise_skyline(skyline: &mut Vec<Segment>, x: u64, width: u64, height: u64) {
+    let end = x + width;
+    let mut new = Vec::with_capacity(skyline.len() + 2);
+    for s in skyline.drain(..) {
+        let s_end = s.x + s.width;
+        if s_end <= x || s.x >= end {
+            new.push(s);
+            continue;
+        }
+        if s.x < x {
+            new.push(Segment {
+                x: s.x,
+                width: x - s.x,
+                height: s.height,
+            });
+        }
+        if s.x <= x {
+            new.push(Segment { x, width, height });
+        }
+        if s_end > end {
+            new.push(Segment {
+                x: end,
+                width: s_end - end,
+                height: s.height,
+            });
+        }
+    }
+    // merge neighbours at the same height
+    for s in new {
+        match skyline.last_mut() {
+            Some(last) if last.height == s.height => last.width += s.width,
+            _ => skyline.push(s),
+        }
+    }
 }
 
 #[test]
 fn test() {
-    assert_eq!(58, total_paper(&parse_input("2x3x4\n")));
-    assert_eq!(43, total_paper(&parse_input("1x1x10\n")));
-    assert_eq!(34, total_ribbon(&parse_input("2x3x4\n")));
-    assert_eq!(14, total_ribbon(&parse_input("1x1x10\n")));
+    let total_paper = |s| total_materials(&parse_input(s).unwrap()).unwrap().paper();
+    let total_ribbon = |s| total_materials(&parse_input(s).unwrap()).unwrap().ribbon();
+    assert_eq!(58, total_paper("2x3x4\n"));
+    assert_eq!(43, total_paper("1x1x10\n"));
+    assert_eq!(34, total_ribbon("2x3x4\n"));
+    assert_eq!(14, total_ribbon("1x1x10\n"));
+
+    let errors = |s| parse_input(s).err().map(|e| e.to_string());
+    assert_eq!(
+        Some("line 2, column 4: expected 3 dimensions, found 2".to_string()),
+        errors("2x3x4\n1x1\n")
+    );
+    assert_eq!(
+        Some("line 1, column 3: dimensions must be at least 1".to_string()),
+        errors("1x0x3\n")
+    );
+    assert_eq!(
+        Some("line 1, column 6: unexpected \" inches\"".to_string()),
+        errors("1x2x3 inches\n")
+    );
+
+    // each of these is fine, but they can't be multiplied or added up
+    assert_eq!(
+        None,
+        parse_input("4294967296x4294967296x1\n").unwrap()[0].materials()
+    );
+    let big = parse_input("1x1x2305843009213693952\n1x1x2305843009213693952\n").unwrap();
+    assert!(big[0].materials().is_some());
+    assert_eq!(None, total_materials(&big));
+    // paper adds up to one too many
+    let one_present = parse_input("2305843009213693951x3x1\n").unwrap();
+    assert_eq!(None, one_present[0].materials());
+    assert_eq!(None, bill_of_materials_csv(&one_present));
+
+    assert_eq!(
+        "\
+present,l,w,h,surface,slack,paper,wrap,bow,ribbon
+1,2,3,4,52,6,58,10,24,34
+2,1,1,10,42,1,43,4,10,14
+total,,,,94,7,101,14,34,48
+",
+        bill_of_materials_csv(&parse_input("2x3x4\n1x1x10\n").unwrap()).unwrap()
+    );
+}
+
+#[test]
+fn test_roll() {
+    let presents = parse_input("1x1x1\n").unwrap();
+    assert_eq!(7, layout_on_roll(&presents, 1).unwrap().length);
+    assert_eq!(3, layout_on_roll(&presents, 3).unwrap().length);
+
+    let presents = parse_input("2x3x4\n1x1x10\n5x1x1\n").unwrap();
+    assert!(layout_on_roll(&presents, 2).is_none());
+    let total_paper = total_materials(&presents).unwrap().paper();
+    for width in [3, 4, 7, 10, 25] {
+        let layout = layout_on_roll(&presents, width).unwrap();
+        assert_eq!(21, layout.placements.len());
+        assert!(layout.length >= layout.theoretical_length(total_paper));
+        // every piece is on the roll, and no two overlap
+        let area: u64 = layout.placements.iter().map(|p| p.width * p.length).sum();
+        assert_eq!(total_paper, area);
+        for (i, a) in layout.placements.iter().enumerate() {
+            assert!(a.x + a.width <= width && a.y + a.length <= layout.length);
+            for b in &layout.placements[i + 1..] {
+                assert!(
+                    a.x + a.width <= b.x
+                        || b.x + b.width <= a.x
+                        || a.y + a.length <= b.y
+                        || b.y + b.length <= a.y
+                );
+            }
+        }
+    }
+
+    let layout = layout_on_roll(&presents, 10).unwrap();
+    assert_eq!(
+        format!(
+            "{} feet of 10 foot roll ({} square feet) for 124 square feet of paper: {:.1}% used, \
+             at least 13 feet needed",
+            layout.length,
+            layout.length * 10,
+            12400.0 / (layout.length * 10) as f64
+        ),
+        layout.report(total_paper)
+    );
 }
@@ -1,3 +1,4 @@
+use crate::parallel;
 use crate::Part;
 
 pub fn run(input: &str, part: Part) -> String {
@@ -12,13 +13,10 @@ pub fn run(input: &str, part: Part) -> String {
 }
 
 fn part1(min_presents: u32) -> u32 {
-    let mut house = 1;
-    loop {
-        if presents(house) >= min_presents {
-            return house;
-        }
-        house += 1;
-    }
+    parallel::find_index(1..u32::MAX as u64, 1000, |house| {
+        presents(house as u32) >= min_presents
+    })
+    .unwrap() as u32
 }
 
 fn part2(min_presents: u32) -> u32 {
@@ -47,7 +45,7 @@ fn presents(house: u32) -> u32 {
     let mut presents = 0;
     let sqrt = (house as f64).sqrt() as u32;
     for elf in 1..=sqrt {
-        if house % elf == 0 {
+        if house.is_multiple_of(elf) {
             if elf * elf == house {
                 presents += elf * 10;
                 continue;
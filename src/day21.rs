@@ -1,5 +1,6 @@
 use itertools::Itertools;
 
+use crate::parse;
 use crate::Part;
 
 pub fn run(input: &str, part: Part) -> String {
@@ -206,13 +207,18 @@ fn parse_input(input: &str) -> Boss {
     let mut hit_points = 0;
     let mut damage = 0;
     let mut armor = 0;
-    for line in input.lines() {
-        let data = line.split(": ").collect::<Vec<_>>();
-        match data[0] {
-            "Hit Points" => hit_points = data[1].parse().unwrap(),
-            "Damage" => damage = data[1].parse().unwrap(),
-            "Armor" => armor = data[1].parse().unwrap(),
-            _ => panic!("Unknown input: {}", line),
+    let stats = parse::lines(input, |p| {
+        let stat = p.until(": ")?;
+        p.literal(": ")?;
+        Ok((stat, p.integer()?))
+    })
+    .unwrap();
+    for (stat, value) in stats {
+        match stat {
+            "Hit Points" => hit_points = value,
+            "Damage" => damage = value,
+            "Armor" => armor = value,
+            _ => panic!("Unknown input: {}", stat),
         }
     }
     Boss {
@@ -1,8 +1,7 @@
+use crate::parse;
+use crate::search::{self, SearchProblem};
 use crate::Part;
 
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
-
 pub fn run(input: &str, part: Part) -> String {
     let state = parse_input(input);
     format!(
@@ -36,11 +35,12 @@ impl Spell {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+// mana spent so far is the cost of the search path, so isn't part of the state; that way the
+// same position reached by different spell orders is only explored once.
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
 struct GameState {
     player_hp: i32,
     player_mana: i32,
-    spent_mana: i32,
     boss_hp: i32,
     boss_damage: i32,
     shield_timer: i32,
@@ -54,7 +54,6 @@ impl GameState {
         Self {
             player_hp,
             player_mana,
-            spent_mana: 0,
             boss_hp,
             boss_damage,
             shield_timer: 0,
@@ -105,7 +104,6 @@ impl GameState {
             if next_state.player_mana < 0 {
                 panic!("Player has negative mana!");
             }
-            next_state.spent_mana += spell.cost();
             match spell {
                 Spell::MagicMissile => next_state.boss_hp -= 4,
                 Spell::Drain => {
@@ -153,53 +151,56 @@ impl GameState {
     }
 }
 
-impl Ord for GameState {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.spent_mana.cmp(&other.spent_mana).reverse()
+impl SearchProblem for GameState {
+    type State = GameState;
+    type Move = Spell;
+
+    fn start(&self) -> GameState {
+        self.clone()
     }
-}
 
-impl PartialOrd for GameState {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    fn is_goal(&self, state: &GameState) -> bool {
+        state.boss_hp <= 0
     }
-}
 
-fn find_cheapest_mana_win(initial_state: &GameState) -> Option<i32> {
-    let mut frontier = BinaryHeap::new();
-    frontier.push(initial_state.clone());
-    while let Some(state) = frontier.pop() {
-        if state.boss_hp <= 0 {
-            panic!("should never pop a state where boss_hp <= 0");
-        }
+    // a move is the player's spell followed by the boss's turn, unless the boss died first
+    fn successors(&self, state: &GameState) -> Vec<(Spell, GameState, u32)> {
+        let mut moves = vec![];
         for spell in state.valid_spells() {
-            let next_state = state.player_turn(spell);
+            let mut next_state = state.player_turn(spell);
             if next_state.player_hp <= 0 {
                 continue;
             }
-            if next_state.boss_hp <= 0 {
-                return Some(next_state.spent_mana);
-            }
-            let next_state = next_state.boss_turn();
-            if next_state.boss_hp <= 0 {
-                return Some(next_state.spent_mana);
-            }
-            if next_state.player_hp <= 0 {
-                continue;
+            if next_state.boss_hp > 0 {
+                next_state = next_state.boss_turn();
+                if next_state.boss_hp > 0 && next_state.player_hp <= 0 {
+                    continue;
+                }
             }
-            frontier.push(next_state);
+            moves.push((spell, next_state, spell.cost() as u32));
         }
+        moves
     }
-    None
+}
+
+fn cheapest_win(initial_state: &GameState) -> Option<search::Path<GameState, Spell>> {
+    search::dijkstra(initial_state)
+}
+
+fn find_cheapest_mana_win(initial_state: &GameState) -> Option<i32> {
+    cheapest_win(initial_state).map(|path| path.cost as i32)
 }
 
 fn parse_input(input: &str) -> GameState {
     let mut boss_hp = 0;
     let mut boss_damage = 0;
-    for line in input.lines() {
-        let mut parts = line.split(": ");
-        let stat = parts.next().unwrap();
-        let value = parts.next().unwrap().parse().unwrap();
+    let stats = parse::lines(input, |p| {
+        let stat = p.until(": ")?;
+        p.literal(": ")?;
+        Ok((stat, p.integer()?))
+    })
+    .unwrap();
+    for (stat, value) in stats {
         match stat {
             "Hit Points" => boss_hp = value,
             "Damage" => boss_damage = value,
@@ -219,4 +220,18 @@ fn test() {
         find_cheapest_mana_win(&GameState::new(10, 250, 14, 8)),
         Some(641)
     );
+    assert_eq!(
+        cheapest_win(&GameState::new(10, 250, 14, 8))
+            .unwrap()
+            .moves()
+            .copied()
+            .collect::<Vec<_>>(),
+        vec![
+            Spell::Recharge,
+            Spell::Shield,
+            Spell::Drain,
+            Spell::Poison,
+            Spell::MagicMissile
+        ]
+    );
 }
@@ -1,5 +1,8 @@
+use crate::image::{self, Image, Rgb};
 use crate::Part;
-use std::collections::HashSet;
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::fmt::Write;
 
 pub fn run(input: &str, part: Part) -> String {
     let input = parse_input(input);
@@ -42,31 +45,210 @@ fn parse_input(input: &str) -> Vec<Dir> {
         .collect()
 }
 
-fn deliver_presents<'a>(houses: &mut HashSet<(i32, i32)>, dirs: impl Iterator<Item = &'a Dir>) {
-    let mut pos = (0, 0); // x,y
-    houses.insert(pos);
-    for dir in dirs {
-        pos = match dir {
-            Dir::North => (pos.0, pos.1 - 1),
-            Dir::East => (pos.0 + 1, pos.1),
-            Dir::South => (pos.0, pos.1 + 1),
-            Dir::West => (pos.0 - 1, pos.1),
+// Every visit to every house, split by which courier made it
+struct Deliveries {
+    houses: HashMap<(i32, i32), Vec<u32>>, // x,y -> visits by each courier
+    routes: Vec<Vec<(i32, i32)>>,          // every house each courier went to, in order
+}
+
+impl Deliveries {
+    // assign gives the courier who follows each direction, by index into dirs; couriers are
+    // numbered from 0 to couriers - 1.  Every courier starts by delivering to the house at 0,0.
+    fn new(dirs: &[Dir], couriers: usize, assign: impl Fn(usize) -> usize) -> Self {
+        assert!(couriers > 0, "no couriers");
+        let mut houses = HashMap::new();
+        let mut routes = vec![vec![(0, 0)]; couriers]; // x,y
+        houses.insert((0, 0), vec![1; couriers]);
+        for (i, dir) in dirs.iter().enumerate() {
+            let c = assign(i);
+            assert!(
+                c < couriers,
+                "direction {} assigned to courier {} of {}",
+                i,
+                c,
+                couriers
+            );
+            let p = *routes[c].last().unwrap();
+            let next = match dir {
+                Dir::North => (p.0, p.1 - 1),
+                Dir::East => (p.0 + 1, p.1),
+                Dir::South => (p.0, p.1 + 1),
+                Dir::West => (p.0 - 1, p.1),
+            };
+            houses.entry(next).or_insert_with(|| vec![0; couriers])[c] += 1;
+            routes[c].push(next);
+        }
+        Self { houses, routes }
+    }
+
+    fn houses_visited(&self) -> usize {
+        self.houses.len()
+    }
+}
+
+#[allow(dead_code)]
+impl Deliveries {
+    // total visits to a house by all couriers
+    fn visits(&self, house: (i32, i32)) -> u32 {
+        self.houses.get(&house).map_or(0, |v| v.iter().sum())
+    }
+
+    // number of houses visited by a courier
+    fn coverage(&self, courier: usize) -> usize {
+        self.houses.values().filter(|v| v[courier] > 0).count()
+    }
+
+    // number of houses visited by exactly k different couriers
+    fn visited_by(&self, k: usize) -> usize {
+        self.houses
+            .values()
+            .filter(|v| v.iter().filter(|&&n| n > 0).count() == k)
+            .count()
+    }
+}
+
+// Pictures of the deliveries.  Each house is a square, coloured by the couriers who visited it
+// and brighter the more visits it had, with each courier's route drawn through the middle of the
+// houses in their colour.
+#[allow(dead_code)]
+impl Deliveries {
+    // (min x, min y, width, height) in houses
+    fn bounds(&self) -> (i32, i32, usize, usize) {
+        let (x0, x1) = self
+            .houses
+            .keys()
+            .map(|h| h.0)
+            .minmax()
+            .into_option()
+            .unwrap();
+        let (y0, y1) = self
+            .houses
+            .keys()
+            .map(|h| h.1)
+            .minmax()
+            .into_option()
+            .unwrap();
+        (x0, y0, (x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize)
+    }
+
+    fn house_colour(&self, visits: &[u32], max_visits: u32) -> Rgb {
+        let total: u32 = visits.iter().sum();
+        let mut mix = [0.0; 3];
+        for (c, &n) in visits.iter().enumerate() {
+            for (m, p) in mix.iter_mut().zip(image::palette(c)) {
+                *m += p as f64 * n as f64 / total as f64;
+            }
+        }
+        // log scale, as a few houses get far more visits than the rest
+        let brightness = if max_visits > 1 {
+            0.3 + 0.7 * (total as f64).ln() / (max_visits as f64).ln()
+        } else {
+            1.0
         };
-        houses.insert(pos);
+        image::dim(mix.map(|m| m.round() as u8), brightness)
+    }
+
+    // each house is scale x scale pixels; routes are only drawn if scale is at least 3
+    fn render(&self, scale: usize) -> Image {
+        let (x0, y0, width, height) = self.bounds();
+        let mut image = Image::new(width * scale, height * scale, image::grey(0));
+        let max_visits = self.houses.values().map(|v| v.iter().sum()).max().unwrap();
+        for (&(x, y), visits) in &self.houses {
+            let px = (x - x0) as usize * scale;
+            let py = (y - y0) as usize * scale;
+            let colour = self.house_colour(visits, max_visits);
+            image.fill(px, py, scale, scale, colour);
+        }
+        if scale >= 3 {
+            let centre = |(x, y): (i32, i32)| {
+                (
+                    (x - x0) as usize * scale + scale / 2,
+                    (y - y0) as usize * scale + scale / 2,
+                )
+            };
+            for (c, route) in self.routes.iter().enumerate() {
+                for step in route.windows(2) {
+                    let (ax, ay) = centre(step[0]);
+                    let (bx, by) = centre(step[1]);
+                    // steps are only ever to a neighbouring house
+                    let (x, y) = (ax.min(bx), ay.min(by));
+                    image.fill(
+                        x,
+                        y,
+                        ax.abs_diff(bx) + 1,
+                        ay.abs_diff(by) + 1,
+                        image::palette(c),
+                    );
+                }
+            }
+        }
+        image
+    }
+
+    fn render_svg(&self, scale: usize) -> String {
+        let (x0, y0, width, height) = self.bounds();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             style=\"background: black\">\n",
+            width * scale,
+            height * scale
+        );
+        let max_visits = self.houses.values().map(|v| v.iter().sum()).max().unwrap();
+        for (&(x, y), visits) in self.houses.iter().sorted() {
+            let [r, g, b] = self.house_colour(visits, max_visits);
+            writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"rgb({},{},{})\"/>",
+                (x - x0) as usize * scale,
+                (y - y0) as usize * scale,
+                scale,
+                scale,
+                r,
+                g,
+                b
+            )
+            .unwrap();
+        }
+        for (c, route) in self.routes.iter().enumerate() {
+            let [r, g, b] = image::palette(c);
+            let points = route
+                .iter()
+                .map(|&(x, y)| {
+                    format!(
+                        "{},{}",
+                        (x - x0) as f64 * scale as f64 + scale as f64 / 2.0,
+                        (y - y0) as f64 * scale as f64 + scale as f64 / 2.0
+                    )
+                })
+                .join(" ");
+            writeln!(
+                svg,
+                "<polyline points=\"{}\" fill=\"none\" stroke=\"rgb({},{},{})\" \
+                 stroke-width=\"{}\"/>",
+                points,
+                r,
+                g,
+                b,
+                scale as f64 / 6.0
+            )
+            .unwrap();
+        }
+        svg.push_str("</svg>\n");
+        svg
     }
 }
 
+fn round_robin(couriers: usize) -> impl Fn(usize) -> usize {
+    assert!(couriers > 0, "no couriers");
+    move |i| i % couriers
+}
+
 fn part1(dirs: &[Dir]) -> usize {
-    let mut houses = HashSet::new();
-    deliver_presents(&mut houses, dirs.iter());
-    houses.len()
+    Deliveries::new(dirs, 1, round_robin(1)).houses_visited()
 }
 
 fn part2(dirs: &[Dir]) -> usize {
-    let mut houses = HashSet::new();
-    deliver_presents(&mut houses, dirs.iter().step_by(2));
-    deliver_presents(&mut houses, dirs.iter().skip(1).step_by(2));
-    houses.len()
+    Deliveries::new(dirs, 2, round_robin(2)).houses_visited()
 }
 
 #[test]
@@ -78,4 +260,65 @@ fn test() {
     assert_eq!(3, part2(&parse_input("^>")));
     assert_eq!(3, part2(&parse_input("^>v<")));
     assert_eq!(11, part2(&parse_input("^v^v^v^v^v")));
+
+    let d = Deliveries::new(&parse_input("^v^v^v^v^v"), 1, round_robin(1));
+    assert_eq!(6, d.visits((0, 0)));
+    assert_eq!(5, d.visits((0, -1)));
+    assert_eq!(0, d.visits((1, 1)));
+
+    let d = Deliveries::new(&parse_input("^>v<"), 2, round_robin(2));
+    assert_eq!(4, d.visits((0, 0)));
+    assert_eq!(vec![2, 2], d.houses[&(0, 0)]);
+    assert_eq!((2, 2), (d.coverage(0), d.coverage(1)));
+    assert_eq!((2, 1), (d.visited_by(1), d.visited_by(2)));
+
+    let d = Deliveries::new(&parse_input("^>v<^>v<"), 3, round_robin(3));
+    assert_eq!(8, d.houses_visited());
+    assert_eq!((4, 4, 3), (d.coverage(0), d.coverage(1), d.coverage(2)));
+    assert_eq!(
+        (6, 1, 1),
+        (d.visited_by(1), d.visited_by(2), d.visited_by(3))
+    );
+
+    // first half of the directions to one courier, the rest to another
+    let d = Deliveries::new(&parse_input("^>v<"), 2, |i| i / 2);
+    assert_eq!(5, d.houses_visited());
+    assert_eq!((3, 3), (d.coverage(0), d.coverage(1)));
+}
+
+#[test]
+#[should_panic(expected = "direction 2 assigned to courier 2 of 2")]
+fn test_bad_assignment() {
+    Deliveries::new(&parse_input("^>v<"), 2, |i| i);
+}
+
+#[test]
+fn test_render() {
+    let d = Deliveries::new(&parse_input("^>v<"), 2, round_robin(2));
+    // 2x2 houses; 0,0 is at the bottom left and visited 4 times, by both couriers
+    let image = d.render(1);
+    assert_eq!((2, 2), (image.width, image.height));
+    assert_eq!(image::grey(0), image.get(1, 0));
+    let mixed = [145, 103, 75]; // halfway between the first two palette colours
+    assert_eq!(mixed, image.get(0, 1));
+    // visited once, by one courier
+    assert_eq!(image::dim(image::palette(0), 0.3), image.get(0, 0));
+    assert_eq!(image::dim(image::palette(1), 0.3), image.get(1, 1));
+
+    // courier 0 goes up from the middle of the 0,0 house, courier 1 to the right
+    let image = d.render(5);
+    assert_eq!((10, 10), (image.width, image.height));
+    assert_eq!(image::palette(0), image.get(2, 4));
+    assert_eq!(image::palette(1), image.get(6, 7));
+    assert_eq!(mixed, image.get(0, 9));
+
+    let svg = d.render_svg(6);
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"12\" height=\"12\""));
+    assert_eq!(3, svg.matches("<rect").count());
+    assert!(
+        svg.contains("<rect x=\"0\" y=\"6\" width=\"6\" height=\"6\" fill=\"rgb(145,103,75)\"/>")
+    );
+    assert!(
+        svg.contains("<polyline points=\"3,9 3,3 3,9\" fill=\"none\" stroke=\"rgb(230,25,75)\"")
+    );
 }
@@ -1,6 +1,11 @@
+use crate::hash::{HashFunction, Md5};
+use crate::parallel::{self, WorkerStats};
 use crate::Part;
-use std::sync::mpsc;
-use std::thread;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
 
 pub fn run(input: &str, part: Part) -> String {
     let key = parse_input(input);
@@ -17,75 +22,279 @@ fn parse_input(input: &str) -> String {
     input.lines().next().unwrap().to_string()
 }
 
-fn search_hash(key: &str, zeros: u32) -> u32 {
-    let mut handles = vec![];
-    // channel used for threads to tell main when they have a result
-    let (tx, rx) = mpsc::channel();
-    let threads = num_cpus::get() as u32;
-    for i in 0..threads {
-        let tx = tx.clone();
-        let key = key.to_owned();
-        // channel used by main to tell threads a result has been found
-        let (tstop, rstop) = mpsc::channel();
-        handles.push((
-            thread::spawn(move || {
-                let mut best: u32 = u32::MAX;
-                let mut iter = 0;
-                loop {
-                    let num = iter * threads + i;
-                    if num > best {
-                        return None;
-                    }
-                    let digest = md5::compute(format!("{}{}", key, num).as_bytes());
-                    if match zeros {
-                        5 => digest[0] == 0 && digest[1] == 0 && digest[2] < 16,
-                        6 => digest[0] == 0 && digest[1] == 0 && digest[2] == 0,
-                        _ => unreachable!(),
-                    } {
-                        let _ = tx.send(num); // don't care if error
-                        return Some(num);
-                    }
-                    iter += 1;
-                    if iter % 10000 == 0 {
-                        if let Ok(n) = rstop.try_recv() {
-                            best = n;
-                        }
-                    }
-                }
-            }),
-            tstop,
-        ));
+// What a digest has to look like for the search to stop
+#[allow(dead_code)]
+#[derive(Clone)]
+enum Target {
+    // this many leading zero hex digits
+    LeadingZeros(usize),
+    // hex representation starts with this (one nibble per element)
+    HexPrefix(Vec<u8>),
+    // digest is less than this, as a big-endian number.  A threshold shorter than the digest is
+    // compared with the start of the digest.
+    Below(Vec<u8>),
+}
+
+impl Target {
+    #[allow(dead_code)]
+    fn hex_prefix(prefix: &str) -> Option<Self> {
+        prefix
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8))
+            .collect::<Option<Vec<u8>>>()
+            .map(Target::HexPrefix)
     }
 
-    // wait until first result has come in
-    let candidate = rx.recv().unwrap();
+    fn matches(&self, digest: &[u8]) -> bool {
+        // high nibble of each byte comes first
+        let nibble = |i: usize| (digest[i / 2] >> (4 - 4 * (i & 1))) & 0xf;
+        match self {
+            Target::LeadingZeros(n) => *n <= digest.len() * 2 && (0..*n).all(|i| nibble(i) == 0),
+            Target::HexPrefix(prefix) => {
+                prefix.len() <= digest.len() * 2
+                    && prefix.iter().enumerate().all(|(i, &d)| nibble(i) == d)
+            }
+            Target::Below(threshold) => digest[..threshold.len().min(digest.len())] < threshold[..],
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hex = |nibbles: &[u8]| {
+            nibbles
+                .iter()
+                .map(|&d| char::from_digit(d as u32, 16).unwrap())
+                .collect::<String>()
+        };
+        match self {
+            Target::LeadingZeros(n) => write!(f, "zeros:{}", n),
+            Target::HexPrefix(prefix) => write!(f, "prefix:{}", hex(prefix)),
+            Target::Below(threshold) => write!(f, "below:{}", crate::hash::hex(threshold)),
+        }
+    }
+}
+
+// Returns the lowest number from start upwards (and its digest) where the digest of the key
+// followed by the number matches the target.
+fn search_hash<H: HashFunction>(key: &str, target: &Target, start: u64) -> (u64, H::Digest) {
+    let search = search_with_progress::<H>(key, target, start, Duration::MAX, |_, _| {});
+    (search.num, search.digest)
+}
+
+struct Search<D> {
+    num: u64,
+    digest: D,
+    threads: Vec<WorkerStats>,
+}
+
+impl<D> Search<D> {
+    #[allow(dead_code)]
+    fn report(&self) -> String {
+        let mut report = format!("found {}\n", self.num);
+        for (i, t) in self.threads.iter().enumerate() {
+            report += &format!(
+                "thread {}: {} hashes in {:.3}s, {:.0} hashes/s\n",
+                i,
+                t.items,
+                t.elapsed.as_secs_f64(),
+                t.per_second()
+            );
+        }
+        report
+    }
+}
 
-    // send this found number to all threads; they will stop as soon as their search couldn't
-    // possibly find anything better.
-    for h in &handles {
-        let _ = h.1.send(candidate); // don't care if error because thread has exited
+// Every interval, progress is called with the number below which everything has been tried and
+// each thread's stats so far.
+fn search_with_progress<H: HashFunction>(
+    key: &str,
+    target: &Target,
+    start: u64,
+    interval: Duration,
+    progress: impl FnMut(u64, &[WorkerStats]),
+) -> Search<H::Digest> {
+    let (found, threads) = parallel::find_first_with_progress(
+        start..u64::MAX,
+        10000,
+        |nums| {
+            H::numbered(key.as_bytes(), nums.start, 1)
+                .take((nums.end - nums.start) as usize)
+                .find(|(_, digest)| target.matches(digest.as_ref()))
+        },
+        interval,
+        progress,
+    );
+    let (num, digest) = found.expect("nothing matched");
+    Search {
+        num,
+        digest,
+        threads,
     }
+}
 
-    // wait for all threads to finish what they're doing then return the lowest number found.
-    // there could be multiple matches if there are results nearby and the wrong number was found
-    // faster than the best one.
-    handles
-        .into_iter()
-        .filter_map(|jh| jh.0.join().unwrap())
-        .min()
-        .unwrap()
+// A search which saves its progress to a file every interval, and carries on from there if run
+// again after being interrupted.  The file records the hash, target and key it belongs to and is
+// ignored if they don't match.
+#[allow(dead_code)]
+fn resumable_search<H: HashFunction>(
+    key: &str,
+    target: &Target,
+    checkpoint: &Path,
+    interval: Duration,
+) -> io::Result<Search<H::Digest>> {
+    let header = format!("{} {} {}", H::NAME, target, key);
+    let start = match fs::read_to_string(checkpoint) {
+        Ok(saved) => match saved.split_once('\n') {
+            Some((h, num)) if h == header => num.trim().parse().unwrap_or(0),
+            _ => 0,
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+        Err(e) => return Err(e),
+    };
+    let mut error = Ok(());
+    let search = search_with_progress::<H>(key, target, start, interval, |tried_below, _| {
+        if error.is_ok() {
+            error = save_checkpoint(checkpoint, &header, tried_below);
+        }
+    });
+    error.map(|_| search)
 }
 
-fn part1(key: &str) -> u32 {
-    search_hash(key, 5)
+// written to a temporary file first so an interruption can't leave half a checkpoint
+fn save_checkpoint(path: &Path, header: &str, tried_below: u64) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, format!("{}\n{}\n", header, tried_below))?;
+    fs::rename(&tmp, path)
 }
 
-fn part2(key: &str) -> u32 {
-    search_hash(key, 6)
+fn part1(key: &str) -> u64 {
+    search_hash::<Md5>(key, &Target::LeadingZeros(5), 0).0
+}
+
+fn part2(key: &str) -> u64 {
+    search_hash::<Md5>(key, &Target::LeadingZeros(6), 0).0
 }
 
 #[test]
 fn test() {
+    use crate::hash::{hex, Sha1, Sha256};
+
     assert_eq!(609043, part1(&parse_input("abcdef\n")));
     assert_eq!(1048970, part1(&parse_input("pqrstuv\n")));
+
+    let (num, digest) = search_hash::<Md5>("abcdef", &Target::hex_prefix("000001dbb").unwrap(), 0);
+    assert_eq!(609043, num);
+    assert_eq!("000001dbbfa3a5c83a2d506429c7b00e", hex(&digest));
+    let threshold = Target::Below(vec![0, 0, 2]);
+    assert_eq!(609043, search_hash::<Md5>("abcdef", &threshold, 0).0);
+
+    // check easier targets (and starting points) against a simple sequential search
+    let targets = [
+        Target::LeadingZeros(2),
+        Target::hex_prefix("abc").unwrap(),
+        Target::Below(vec![0x01; 20]),
+    ];
+    for target in &targets {
+        for start in [0, 1, 5000] {
+            let expected = |digest: fn(&[u8]) -> Vec<u8>| {
+                (start..)
+                    .find(|n| target.matches(&digest(format!("abcdef{}", n).as_bytes())))
+                    .unwrap()
+            };
+            assert_eq!(
+                expected(|d| Md5::digest(d).to_vec()),
+                search_hash::<Md5>("abcdef", target, start).0
+            );
+            assert_eq!(
+                expected(|d| Sha1::digest(d).to_vec()),
+                search_hash::<Sha1>("abcdef", target, start).0
+            );
+            assert_eq!(
+                expected(|d| Sha256::digest(d).to_vec()),
+                search_hash::<Sha256>("abcdef", target, start).0
+            );
+        }
+    }
+    assert!(Target::hex_prefix("00g").is_none());
+    assert!(!Target::LeadingZeros(33).matches(&[0; 16]));
+    assert!(Target::LeadingZeros(33).matches(&[0; 20]));
+}
+
+#[test]
+fn test_resume() {
+    let path = std::env::temp_dir().join(format!("day4-checkpoint-{}", std::process::id()));
+    let target = Target::hex_prefix("0000").unwrap();
+    let search =
+        resumable_search::<Md5>("abcdef", &target, &path, Duration::from_millis(1)).unwrap();
+    assert_eq!(31556, search.num);
+    assert_eq!(
+        "md5 prefix:0000 abcdef\n31556\n",
+        fs::read_to_string(&path).unwrap()
+    );
+    assert!(search.threads.iter().map(|t| t.items).sum::<u64>() >= 31556);
+    assert!(search.report().starts_with("found 31556\nthread 0: "));
+
+    // pick up from a later checkpoint, but not one for a different search
+    save_checkpoint(&path, "md5 prefix:0000 abcdef", 31557).unwrap();
+    let search =
+        resumable_search::<Md5>("abcdef", &target, &path, Duration::from_secs(60)).unwrap();
+    let expected = (31557..)
+        .find(|n| target.matches(&Md5::digest(format!("abcdef{}", n).as_bytes())))
+        .unwrap();
+    assert_eq!(expected, search.num);
+    save_checkpoint(&path, "sha1 prefix:0000 abcdef", 31557).unwrap();
+    let search =
+        resumable_search::<Md5>("abcdef", &target, &path, Duration::from_secs(60)).unwrap();
+    assert_eq!(31556, search.num);
+    fs::remove_file(&path).unwrap();
+}
+
+// cargo test --release bench_hash_loop -- --ignored --nocapture
+#[test]
+#[ignore]
+fn bench_hash_loop() {
+    use crate::hash::Md5Prefix;
+    use std::time::Instant;
+
+    let key = "abcdef";
+    let count = 10_000_000;
+    let time = |name: &str, f: &dyn Fn() -> u64| {
+        let t0 = Instant::now();
+        let zeros = f();
+        let secs = t0.elapsed().as_secs_f64();
+        println!(
+            "{:10} {:.3}s, {:.0} hashes/s",
+            name,
+            secs,
+            count as f64 / secs
+        );
+        zeros
+    };
+    let zeros = |d: &[u8; 16]| Target::LeadingZeros(4).matches(d) as u64;
+    // single thread, counting digests with 4 leading zeros so nothing is optimised away
+    let expected = time("format", &|| {
+        (0..count)
+            .map(|n| zeros(&md5::compute(format!("{}{}", key, n)).0))
+            .sum()
+    });
+    let prefix = Md5Prefix::new(key.as_bytes());
+    assert_eq!(
+        expected,
+        time("prefix", &|| {
+            (0..count)
+                .map(|n| zeros(&prefix.digest(n.to_string().as_bytes())))
+                .sum()
+        })
+    );
+    assert_eq!(
+        expected,
+        time("lanes", &|| {
+            Md5::numbered(key.as_bytes(), 0, 1)
+                .take(count as usize)
+                .map(|(_, d)| zeros(&d))
+                .sum()
+        })
+    );
 }
@@ -1,79 +1,658 @@
+use crate::parse::{self, ParseError, Parser};
 use crate::Part;
+use std::collections::{HashMap, VecDeque};
 
 pub fn run(input: &str, part: Part) -> String {
     let words = parse_input(input);
-    format!(
-        "{}",
-        match part {
-            Part::One => part1(&words),
-            Part::Two => part2(&words),
+    let rules = Rules::parse(match part {
+        Part::One => PART1_RULES,
+        Part::Two => PART2_RULES,
+    })
+    .unwrap();
+    format!("{}", count_nice(&mut rules.compile(), &words))
+}
+
+// Rule files have one named rule per line, each of which can use the ones before it.  The last
+// rule decides whether a word is nice.  Blank lines and lines starting with # are ignored.
+//
+//   name = at least N of "letters"        N or more letters (counting repeats) from the set
+//   name = letter repeated with gap K      some letter appears again K letters later
+//   name = repeated N-gram                 some N letters appear twice without overlapping
+//   name = none of "ab", "cd"              none of these substrings appear
+//
+// Rules combine with not, and, or (binding in that order) and parentheses.
+
+const PART1_RULES: &str = r#"
+vowels = at least 3 of "aeiou"
+double = letter repeated with gap 0
+clean = none of "ab", "cd", "pq", "xy"
+nice = vowels and double and clean
+"#;
+
+const PART2_RULES: &str = r#"
+pair = repeated 2-gram
+sandwich = letter repeated with gap 1
+nice = pair and sandwich
+"#;
+
+#[derive(Debug)]
+enum Rule {
+    AtLeast(usize, Vec<char>),
+    Gap(usize),
+    RepeatedGram(usize),
+    NoneOf(Vec<String>),
+    Named(usize), // index of an earlier rule
+    Not(Box<Rule>),
+    And(Vec<Rule>),
+    Or(Vec<Rule>),
+}
+
+impl Rule {
+    // rules are the ones defined so far, for looking up Named
+    fn matches(&self, word: &str, chars: &[char], rules: &[Rule]) -> bool {
+        match self {
+            Rule::AtLeast(n, set) => chars.iter().filter(|c| set.contains(c)).count() >= *n,
+            Rule::Gap(k) => chars.windows(k + 2).any(|w| w[0] == w[k + 1]),
+            Rule::RepeatedGram(n) => contains_repeated_gram(chars, *n),
+            Rule::NoneOf(substrings) => !substrings.iter().any(|s| word.contains(s.as_str())),
+            Rule::Named(i) => rules[*i].matches(word, chars, rules),
+            Rule::Not(rule) => !rule.matches(word, chars, rules),
+            Rule::And(all) => all.iter().all(|r| r.matches(word, chars, rules)),
+            Rule::Or(any) => any.iter().any(|r| r.matches(word, chars, rules)),
         }
-    )
+    }
+}
+
+// Keeping the first place each n-gram is seen means a later copy is checked against the one
+// furthest away.
+fn contains_repeated_gram(chars: &[char], n: usize) -> bool {
+    let mut first = HashMap::new();
+    for (i, gram) in chars.windows(n).enumerate() {
+        match first.get(gram) {
+            Some(&j) if i >= j + n => return true,
+            Some(_) => {}
+            None => {
+                first.insert(gram, i);
+            }
+        }
+    }
+    false
+}
+
+struct Rules {
+    names: Vec<String>,
+    rules: Vec<Rule>,
+}
+
+impl Rules {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut names: Vec<String> = vec![];
+        let rules = parse::definitions(input, "rules", |p| {
+            spaces(p);
+            let start = p.position();
+            let name = p.ident()?;
+            if names.iter().any(|n| n == name) {
+                return Err(p.error_at(start, format!("rule {:?} is already defined", name)));
+            }
+            spaces(p);
+            p.literal("=")?;
+            spaces(p);
+            let rule = parse_or(p, &names)?;
+            spaces(p);
+            names.push(name.to_string());
+            Ok(rule)
+        })?;
+        Ok(Self { names, rules })
+    }
+
+    // checks each rule directly; compile is quicker for lots of words
+    #[allow(dead_code)]
+    fn is_nice(&self, word: &str) -> bool {
+        let chars: Vec<char> = word.chars().collect();
+        self.rules
+            .last()
+            .unwrap()
+            .matches(word, &chars, &self.rules)
+    }
+
+    fn compile(&self) -> Classifier {
+        let mut classifier = Classifier {
+            dfa: Dfa::new(),
+            grams: vec![],
+            expr: Expr::And(vec![]),
+        };
+        // each rule compiled once, so rules used by several others share their machines
+        let mut compiled: Vec<Expr> = vec![];
+        for rule in &self.rules {
+            let expr = classifier.add(rule, &compiled);
+            compiled.push(expr);
+        }
+        classifier.expr = compiled.pop().unwrap();
+        classifier.dfa.start();
+        classifier
+    }
+
+    // whether the word passes each rule, e.g. "aaa: vowels pass, double pass, clean pass, nice pass"
+    #[allow(dead_code)]
+    fn report(&self, word: &str) -> String {
+        let chars: Vec<char> = word.chars().collect();
+        let results: Vec<String> = self
+            .names
+            .iter()
+            .zip(&self.rules)
+            .map(|(name, rule)| {
+                let pass = rule.matches(word, &chars, &self.rules);
+                format!("{} {}", name, if pass { "pass" } else { "fail" })
+            })
+            .collect();
+        format!("{}: {}", word, results.join(", "))
+    }
+}
+
+fn spaces(p: &mut Parser) {
+    p.take_while(|c| c == ' ' || c == '\t');
+}
+
+// a whole word, so "nothing" doesn't start with "not"
+fn try_word(p: &mut Parser, word: &str) -> bool {
+    let boundary = p.rest().strip_prefix(word).is_some_and(|after| {
+        after
+            .chars()
+            .next()
+            .is_none_or(|c| !c.is_alphanumeric() && c != '_')
+    });
+    boundary && p.try_literal(word)
 }
 
-fn count_vowels(chars: &[char]) -> usize {
-    chars
-        .iter()
-        .filter(|&&c| c == 'a' || c == 'e' || c == 'i' || c == 'o' || c == 'u')
-        .count()
+fn expect_words(p: &mut Parser, words: &[&str]) -> Result<(), ParseError> {
+    for word in words {
+        spaces(p);
+        if !try_word(p, word) {
+            return Err(p.error(format!("expected {:?}", word)));
+        }
+    }
+    spaces(p);
+    Ok(())
 }
 
-fn contains_double(chars: &[char]) -> bool {
-    chars.windows(2).any(|pair| pair[0] == pair[1])
+fn parse_or(p: &mut Parser, names: &[String]) -> Result<Rule, ParseError> {
+    let mut any = vec![parse_and(p, names)?];
+    while {
+        spaces(p);
+        try_word(p, "or")
+    } {
+        spaces(p);
+        any.push(parse_and(p, names)?);
+    }
+    Ok(if any.len() == 1 {
+        any.pop().unwrap()
+    } else {
+        Rule::Or(any)
+    })
 }
 
-fn contains_bad(s: &str) -> bool {
-    s.contains("ab") || s.contains("cd") || s.contains("pq") || s.contains("xy")
+fn parse_and(p: &mut Parser, names: &[String]) -> Result<Rule, ParseError> {
+    let mut all = vec![parse_not(p, names)?];
+    while {
+        spaces(p);
+        try_word(p, "and")
+    } {
+        spaces(p);
+        all.push(parse_not(p, names)?);
+    }
+    Ok(if all.len() == 1 {
+        all.pop().unwrap()
+    } else {
+        Rule::And(all)
+    })
 }
 
-fn is_nice(s: &str) -> bool {
-    let chars: Vec<char> = s.chars().collect();
-    count_vowels(&chars) >= 3 && contains_double(&chars) && !contains_bad(s)
+fn parse_not(p: &mut Parser, names: &[String]) -> Result<Rule, ParseError> {
+    if try_word(p, "not") {
+        spaces(p);
+        return Ok(Rule::Not(Box::new(parse_not(p, names)?)));
+    }
+    if p.try_literal("(") {
+        spaces(p);
+        let rule = parse_or(p, names)?;
+        spaces(p);
+        p.literal(")")?;
+        return Ok(rule);
+    }
+    if try_word(p, "at") {
+        expect_words(p, &["least"])?;
+        let n = p.integer()?;
+        expect_words(p, &["of"])?;
+        return Ok(Rule::AtLeast(n, quoted(p)?.chars().collect()));
+    }
+    if try_word(p, "letter") {
+        expect_words(p, &["repeated", "with", "gap"])?;
+        return Ok(Rule::Gap(p.integer()?));
+    }
+    if try_word(p, "repeated") {
+        spaces(p);
+        let start = p.position();
+        let n = p.integer()?;
+        if n == 0 {
+            return Err(p.error_at(start, "n-grams must be at least 1 letter"));
+        }
+        p.literal("-gram")?;
+        return Ok(Rule::RepeatedGram(n));
+    }
+    if try_word(p, "none") {
+        expect_words(p, &["of"])?;
+        let substrings = p.separated(",", |p| {
+            spaces(p);
+            let s = quoted(p)?;
+            spaces(p);
+            Ok(s.to_string())
+        })?;
+        return Ok(Rule::NoneOf(substrings));
+    }
+    let start = p.position();
+    let name = p.ident()?;
+    match names.iter().position(|n| n == name) {
+        Some(i) => Ok(Rule::Named(i)),
+        None => Err(p.error_at(start, format!("unknown rule {:?}", name))),
+    }
 }
 
-fn contains_non_overlapping_pair(chars: &[char]) -> bool {
-    for i in 0..(chars.len() - 2) {
-        let pair = (chars[i], chars[i + 1]);
-        for j in (i + 2)..(chars.len() - 1) {
-            if pair.0 == chars[j] && pair.1 == chars[j + 1] {
-                return true;
+fn quoted<'a>(p: &mut Parser<'a>) -> Result<&'a str, ParseError> {
+    p.literal("\"")?;
+    let s = p.until("\"")?;
+    p.literal("\"")?;
+    Ok(s)
+}
+
+// Compiled rules, for classifying lots of words with one pass over each.
+//
+// Every rule except repeated n-grams only needs to remember a bounded amount about the letters seen
+// so far, so they're regular and run as one DFA whose states are tuples of each rule's own state.
+// The DFA is built lazily, as the words being classified reach new states, since most of the
+// possible combinations never happen.  Repeated n-grams run alongside, remembering where each
+// n-gram was first seen.
+
+struct Classifier {
+    dfa: Dfa,
+    grams: Vec<GramMachine>,
+    expr: Expr,
+}
+
+// a rule in terms of the machines that decide its parts
+#[derive(Clone)]
+enum Expr {
+    Regular(usize), // index into the DFA's machines
+    Gram(usize),    // index into grams
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, regular: &[bool], grams: &[GramMachine]) -> bool {
+        match self {
+            Expr::Regular(i) => regular[*i],
+            Expr::Gram(i) => grams[*i].found,
+            Expr::Not(e) => !e.eval(regular, grams),
+            Expr::And(all) => all.iter().all(|e| e.eval(regular, grams)),
+            Expr::Or(any) => any.iter().any(|e| e.eval(regular, grams)),
+        }
+    }
+}
+
+impl Classifier {
+    fn add(&mut self, rule: &Rule, compiled: &[Expr]) -> Expr {
+        let mut regular = |machine| {
+            self.dfa.machines.push(machine);
+            Expr::Regular(self.dfa.machines.len() - 1)
+        };
+        match rule {
+            Rule::AtLeast(n, set) => regular(Machine::AtLeast(*n, set.clone())),
+            Rule::Gap(k) => regular(Machine::Gap(*k)),
+            Rule::NoneOf(substrings) => regular(Machine::NoneOf(substrings.clone())),
+            Rule::RepeatedGram(n) => {
+                self.grams.push(GramMachine::new(*n));
+                Expr::Gram(self.grams.len() - 1)
             }
+            Rule::Named(i) => compiled[*i].clone(),
+            Rule::Not(rule) => Expr::Not(Box::new(self.add(rule, compiled))),
+            Rule::And(all) => Expr::And(all.iter().map(|r| self.add(r, compiled)).collect()),
+            Rule::Or(any) => Expr::Or(any.iter().map(|r| self.add(r, compiled)).collect()),
         }
     }
-    false
+
+    fn is_nice(&mut self, word: &str) -> bool {
+        for g in &mut self.grams {
+            g.reset();
+        }
+        let mut state = 0;
+        for c in word.chars() {
+            state = self.dfa.next(state, c);
+            for g in &mut self.grams {
+                g.step(c);
+            }
+        }
+        self.expr
+            .eval(&self.dfa.accepts[state as usize], &self.grams)
+    }
+}
+
+// One regular rule.  Once a rule's outcome can't change its state becomes Found, so that words
+// which have settled it share DFA states.
+enum Machine {
+    AtLeast(usize, Vec<char>),
+    Gap(usize),
+    NoneOf(Vec<String>),
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum MachineState {
+    Count(usize),      // AtLeast: matching letters so far, up to the number needed
+    Window(Vec<char>), // Gap: the last gap + 1 letters
+    Suffix(String),    // NoneOf: the longest end of the word that starts one of the substrings
+    Found,             // enough letters, a repeated letter, or a forbidden substring
+}
+
+impl Machine {
+    fn start(&self) -> MachineState {
+        match self {
+            Machine::AtLeast(0, _) => MachineState::Found,
+            Machine::AtLeast(_, _) => MachineState::Count(0),
+            Machine::Gap(_) => MachineState::Window(vec![]),
+            Machine::NoneOf(substrings) if substrings.iter().any(|s| s.is_empty()) => {
+                MachineState::Found
+            }
+            Machine::NoneOf(_) => MachineState::Suffix(String::new()),
+        }
+    }
+
+    fn step(&self, state: &MachineState, c: char) -> MachineState {
+        match (self, state) {
+            (_, MachineState::Found) => MachineState::Found,
+            (Machine::AtLeast(n, set), MachineState::Count(count)) => {
+                let count = count + set.contains(&c) as usize;
+                if count >= *n {
+                    MachineState::Found
+                } else {
+                    MachineState::Count(count)
+                }
+            }
+            (Machine::Gap(k), MachineState::Window(window)) => {
+                if window.len() == k + 1 && window[0] == c {
+                    return MachineState::Found;
+                }
+                let mut window = window.clone();
+                window.push(c);
+                if window.len() > k + 1 {
+                    window.remove(0);
+                }
+                MachineState::Window(window)
+            }
+            (Machine::NoneOf(substrings), MachineState::Suffix(suffix)) => {
+                // as in Knuth-Morris-Pratt, anything ending here has to be an end of this
+                let mut suffix = format!("{}{}", suffix, c);
+                if substrings.iter().any(|s| suffix.ends_with(s.as_str())) {
+                    return MachineState::Found;
+                }
+                while !substrings.iter().any(|s| s.starts_with(suffix.as_str())) {
+                    suffix.remove(0);
+                }
+                MachineState::Suffix(suffix)
+            }
+            _ => unreachable!("state from a different machine"),
+        }
+    }
+
+    fn accepts(&self, state: &MachineState) -> bool {
+        match self {
+            Machine::NoneOf(_) => *state != MachineState::Found,
+            _ => *state == MachineState::Found,
+        }
+    }
+}
+
+const UNBUILT: u32 = u32::MAX;
+
+struct Dfa {
+    machines: Vec<Machine>,
+    // each DFA state is the state of every machine; state 0 is the start
+    states: Vec<Vec<MachineState>>,
+    ids: HashMap<Vec<MachineState>, u32>,
+    // for each state, whether each machine accepts there
+    accepts: Vec<Vec<bool>>,
+    // transitions on ASCII letters in a table, anything else in a map
+    ascii: Vec<[u32; 128]>,
+    other: HashMap<(u32, char), u32>,
+}
+
+impl Dfa {
+    fn new() -> Self {
+        Self {
+            machines: vec![],
+            states: vec![],
+            ids: HashMap::new(),
+            accepts: vec![],
+            ascii: vec![],
+            other: HashMap::new(),
+        }
+    }
+
+    // called once all the machines have been added
+    fn start(&mut self) {
+        let start = self.machines.iter().map(|m| m.start()).collect();
+        self.state_id(start);
+    }
+
+    fn state_id(&mut self, state: Vec<MachineState>) -> u32 {
+        if let Some(&id) = self.ids.get(&state) {
+            return id;
+        }
+        let id = self.states.len() as u32;
+        self.accepts.push(
+            self.machines
+                .iter()
+                .zip(&state)
+                .map(|(m, s)| m.accepts(s))
+                .collect(),
+        );
+        self.ascii.push([UNBUILT; 128]);
+        self.ids.insert(state.clone(), id);
+        self.states.push(state);
+        id
+    }
+
+    fn next(&mut self, id: u32, c: char) -> u32 {
+        let known = if c.is_ascii() {
+            self.ascii[id as usize][c as usize]
+        } else {
+            self.other.get(&(id, c)).copied().unwrap_or(UNBUILT)
+        };
+        if known != UNBUILT {
+            return known;
+        }
+        let next: Vec<MachineState> = self
+            .machines
+            .iter()
+            .zip(&self.states[id as usize])
+            .map(|(m, s)| m.step(s, c))
+            .collect();
+        let next = self.state_id(next);
+        if c.is_ascii() {
+            self.ascii[id as usize][c as usize] = next;
+        } else {
+            self.other.insert((id, c), next);
+        }
+        next
+    }
 }
 
-fn contains_sandwiched_letter(chars: &[char]) -> bool {
-    chars.windows(3).any(|slice| slice[0] == slice[2])
+// Finds an n-gram that appears twice without overlapping, in one pass with the first position of
+// each n-gram seen so far.  N-grams of up to 6 letters are packed into an integer to look up.
+struct GramMachine {
+    n: usize,
+    window: VecDeque<char>, // the last n letters
+    first: HashMap<Vec<char>, usize>,
+    first_packed: HashMap<u128, usize>,
+    pos: usize, // letters seen
+    found: bool,
 }
 
-fn is_nice2(s: &str) -> bool {
-    let chars: Vec<char> = s.chars().collect();
-    contains_non_overlapping_pair(&chars) && contains_sandwiched_letter(&chars)
+impl GramMachine {
+    fn new(n: usize) -> Self {
+        Self {
+            n,
+            window: VecDeque::new(),
+            first: HashMap::new(),
+            first_packed: HashMap::new(),
+            pos: 0,
+            found: false,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+        self.first.clear();
+        self.first_packed.clear();
+        self.pos = 0;
+        self.found = false;
+    }
+
+    fn step(&mut self, c: char) {
+        if self.found {
+            return;
+        }
+        self.window.push_back(c);
+        if self.window.len() > self.n {
+            self.window.pop_front();
+        }
+        self.pos += 1;
+        if self.window.len() < self.n {
+            return;
+        }
+        let start = self.pos - self.n;
+        let first = if self.n <= 6 {
+            // chars are at most 21 bits
+            let key = self.window.iter().fold(0, |k, &c| k << 21 | c as u128);
+            *self.first_packed.entry(key).or_insert(start)
+        } else {
+            let key: Vec<char> = self.window.iter().copied().collect();
+            *self.first.entry(key).or_insert(start)
+        };
+        self.found = start >= first + self.n;
+    }
 }
 
 fn parse_input(input: &str) -> Vec<&str> {
     input.lines().collect()
 }
 
-fn part1(words: &[&str]) -> usize {
-    words.iter().filter(|word| is_nice(word)).count()
+fn count_nice(classifier: &mut Classifier, words: &[&str]) -> usize {
+    words.iter().filter(|word| classifier.is_nice(word)).count()
 }
 
-fn part2(words: &[&str]) -> usize {
-    words.iter().filter(|word| is_nice2(word)).count()
+#[test]
+fn test() {
+    let part1 = Rules::parse(PART1_RULES).unwrap();
+    assert!(part1.is_nice("ugknbfddgicrmopn"));
+    assert!(part1.is_nice("aaa"));
+    assert!(!part1.is_nice("jchzalrnumimnmhp"));
+    assert!(!part1.is_nice("haegwjzuvuyypxyu"));
+    assert!(!part1.is_nice("dvszwmarrgswjxmb"));
+    assert_eq!(
+        "haegwjzuvuyypxyu: vowels pass, double pass, clean fail, nice fail",
+        part1.report("haegwjzuvuyypxyu")
+    );
+
+    let part2 = Rules::parse(PART2_RULES).unwrap();
+    assert!(part2.is_nice("qjhvhtzxzqqjkmpb"));
+    assert!(part2.is_nice("xxyxx"));
+    assert!(!part2.is_nice("uurcxstgmygtbstg"));
+    assert!(!part2.is_nice("ieodomkazucvgmuy"));
+    assert!(!part2.is_nice("aaa"));
+    assert!(part2.is_nice("aaaa"));
+
+    let rules = Rules::parse(
+        "# comment\n\
+         a = letter repeated with gap 2\n\
+         \n\
+         b = not (a or none of \"x\") and at least 2 of \"xyz\"\n",
+    )
+    .unwrap();
+    assert!(rules.is_nice("xy"));
+    assert!(rules.is_nice("xyx"));
+    assert!(!rules.is_nice("xyzx"));
+    assert!(!rules.is_nice("xabx"));
+    assert!(!rules.is_nice("yy"));
+
+    let error = |s| Rules::parse(s).err().map(|e| e.to_string());
+    assert_eq!(
+        Some("line 2, column 10: unknown rule \"b\"".to_string()),
+        error("a = repeated 3-gram\nb = a or b\n")
+    );
+    assert_eq!(
+        Some("line 1, column 26: expected \"gap\"".to_string()),
+        error("a = letter repeated with 2\n")
+    );
+    assert_eq!(
+        Some("line 1, column 5: expected a name".to_string()),
+        error("a = \n")
+    );
+    assert_eq!(Some("no rules".to_string()), error("# nothing\n"));
+
+    // names needn't be ASCII
+    let rules = Rules::parse("ééé = at least 1 of \"a\"\nnice = ééé\n");
+    assert!(rules.unwrap().is_nice("ba"));
 }
 
 #[test]
-fn test() {
-    assert!(is_nice("ugknbfddgicrmopn"));
-    assert!(is_nice("aaa"));
-    assert!(!is_nice("jchzalrnumimnmhp"));
-    assert!(!is_nice("haegwjzuvuyypxyu"));
-    assert!(!is_nice("dvszwmarrgswjxmb"));
-
-    assert!(is_nice2("qjhvhtzxzqqjkmpb"));
-    assert!(is_nice2("xxyxx"));
-    assert!(!is_nice2("uurcxstgmygtbstg"));
-    assert!(!is_nice2("ieodomkazucvgmuy"));
+fn test_compiled() {
+    use crate::rng::Rng;
+
+    let sets = [
+        PART1_RULES,
+        PART2_RULES,
+        "a = at least 2 of \"xy\"\n\
+         b = none of \"xyx\", \"yy\", \"\u{e9}z\"\n\
+         c = repeated 3-gram or not letter repeated with gap 2\n\
+         d = (a or b) and not (c and b)\n",
+        "a = none of \"\", \"x\"\nb = at least 0 of \"\" or a\n",
+        "a = repeated 7-gram or repeated 1-gram and not repeated 3-gram\n",
+    ];
+    let words = [
+        "ugknbfddgicrmopn",
+        "aaa",
+        "jchzalrnumimnmhp",
+        "haegwjzuvuyypxyu",
+        "dvszwmarrgswjxmb",
+        "qjhvhtzxzqqjkmpb",
+        "xxyxx",
+        "uurcxstgmygtbstg",
+        "ieodomkazucvgmuy",
+        "",
+        "\u{e9}z\u{e9}zyxyx",
+    ];
+    // random words from a few letters, so every rule matches some of them
+    let mut rng = Rng::new(1);
+    let mut random: Vec<String> = (0..20000)
+        .map(|i| {
+            (0..i % 17)
+                .map(|_| {
+                    ['a', 'b', 'c', 'd', 'e', 'p', 'q', 'x', 'y', 'z', '\u{e9}'][rng.below(11)]
+                })
+                .collect()
+        })
+        .collect();
+    random.extend(words.iter().map(|w| w.to_string()));
+    for rules in sets {
+        let rules = Rules::parse(rules).unwrap();
+        let mut classifier = rules.compile();
+        for word in &random {
+            assert_eq!(rules.is_nice(word), classifier.is_nice(word), "{:?}", word);
+        }
+    }
+
+    // only the states that were needed get built
+    let mut classifier = Rules::parse(PART1_RULES).unwrap().compile();
+    assert_eq!(2, count_nice(&mut classifier, &words[..5]));
+    assert!(classifier.dfa.states.len() < 200);
 }
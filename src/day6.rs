@@ -1,72 +1,872 @@
+use crate::image::{self, Image};
+use crate::parse::{self, ParseError, Parser};
 use crate::Part;
-use lazy_static::lazy_static;
-use regex::Regex;
+use std::cell::OnceCell;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 pub fn run(input: &str, part: Part) -> String {
-    let instructions = parse_input(input);
+    let actions = ActionTable::parse(match part {
+        Part::One => PART1_ACTIONS,
+        Part::Two => PART2_ACTIONS,
+    })
+    .unwrap();
+    let instructions = parse_input(input, &actions);
     format!(
         "{}",
-        match part {
-            Part::One => part1(&instructions),
-            Part::Two => part2(&instructions),
-        }
+        LightGrid::from_instructions(&instructions, &actions).total()
     )
 }
 
-#[derive(Debug)]
-enum Action {
-    TurnOn,
-    Toggle,
-    TurnOff,
+// Action tables say what each phrase in the instructions does to the lights it covers, one phrase
+// per line:
+//
+//   phrase: step, step, ...
+//
+// The steps are applied in order, and each is one of
+//
+//   set N          the value becomes N
+//   add N          N can be negative
+//   multiply N
+//   invert N       the value becomes N minus the value, so `invert 1` switches between 0 and 1
+//   clamp LO..HI   the value is raised to LO or lowered to HI; either can be left out
+//
+// After every step a light's value is kept between 0 (off) and u32::MAX, so `add -1` stops at
+// off.  Blank lines and lines starting with # are ignored.
+
+const PART1_ACTIONS: &str = r#"
+turn on: set 1
+toggle: invert 1
+turn off: set 0
+"#;
+
+const PART2_ACTIONS: &str = r#"
+turn on: add 1
+toggle: add 2
+turn off: add -1
+"#;
+
+#[derive(Clone, Debug)]
+struct ActionTable {
+    actions: Vec<(String, Effect)>, // by phrase
+}
+
+impl ActionTable {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut actions: Vec<(String, Effect)> = vec![];
+        parse::definitions(input, "actions", |p| {
+            let start = p.position();
+            let phrase = p.until(": ")?;
+            if actions.iter().any(|(a, _)| a == phrase) {
+                return Err(p.error_at(start, format!("action {:?} is already defined", phrase)));
+            }
+            p.literal(": ")?;
+            let effect = p
+                .separated(", ", parse_step)?
+                .into_iter()
+                .fold(Effect::IDENTITY, Effect::then);
+            actions.push((phrase.to_string(), effect));
+            Ok(())
+        })?;
+        Ok(Self { actions })
+    }
+
+    // Instructions must use phrases from the table they're run with, which they will if they were
+    // parsed with it.
+    fn effect(&self, phrase: &str) -> Effect {
+        match self.actions.iter().find(|(a, _)| a == phrase) {
+            Some(&(_, effect)) => effect,
+            None => panic!("no action {:?}", phrase),
+        }
+    }
+}
+
+fn parse_step(p: &mut Parser) -> Result<Effect, ParseError> {
+    #[derive(Clone)]
+    enum Step {
+        Set,
+        Add,
+        Multiply,
+        Invert,
+        Clamp,
+    }
+    let step = p.keyword(&[
+        ("set ", Step::Set),
+        ("add ", Step::Add),
+        ("multiply ", Step::Multiply),
+        ("invert ", Step::Invert),
+        ("clamp ", Step::Clamp),
+    ])?;
+    let number = |p: &mut Parser| p.integer::<i64>().map(|n| n as i128);
+    Ok(match step {
+        Step::Set => Effect::constant(number(p)?),
+        Step::Add => Effect::linear(1, number(p)?),
+        Step::Multiply => Effect::linear(number(p)?, 0),
+        Step::Invert => Effect::linear(-1, number(p)?),
+        Step::Clamp => {
+            let start = p.position();
+            let is_number = |p: &Parser| {
+                p.rest()
+                    .starts_with(|c: char| c.is_ascii_digit() || c == '-')
+            };
+            let lo = if is_number(p) { number(p)? } else { 0 };
+            p.literal("..")?;
+            let hi = if is_number(p) { number(p)? } else { MAX_VALUE };
+            if lo > hi {
+                return Err(p.error_at(start, "the range is empty"));
+            }
+            Effect {
+                lo: lo.clamp(0, MAX_VALUE),
+                hi: hi.clamp(0, MAX_VALUE),
+                ..Effect::IDENTITY
+            }
+        }
+    })
 }
 
-impl From<&str> for Action {
-    fn from(s: &str) -> Self {
-        match s {
-            "turn on" => Action::TurnOn,
-            "toggle" => Action::Toggle,
-            "turn off" => Action::TurnOff,
-            _ => panic!("unexpected action: {}", s),
+// Light values go from 0 to this
+const MAX_VALUE: i128 = u32::MAX as i128;
+
+// What an action does to a light's value x: clamp(k * x + a, lo, hi), where 0 <= lo <= hi <=
+// MAX_VALUE.  Every step is one of these and so is one followed by another, so an action of
+// several steps is too, and so is a run of actions (which the sweep below relies on).
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Effect {
+    k: i128,
+    a: i128,
+    lo: i128,
+    hi: i128,
+}
+
+impl Effect {
+    const IDENTITY: Self = Self {
+        k: 1,
+        a: 0,
+        lo: 0,
+        hi: MAX_VALUE,
+    };
+
+    fn constant(c: i128) -> Self {
+        let c = c.clamp(0, MAX_VALUE);
+        Self {
+            k: 0,
+            a: c,
+            lo: c,
+            hi: c,
+        }
+    }
+
+    fn linear(k: i128, a: i128) -> Self {
+        Self {
+            k,
+            a,
+            ..Self::IDENTITY
+        }
+        .normalise()
+    }
+
+    fn apply(self, x: u32) -> u32 {
+        self.at(x as i128) as u32
+    }
+
+    fn at(self, x: i128) -> i128 {
+        (self.k * x + self.a).clamp(self.lo, self.hi)
+    }
+
+    // self followed by next
+    fn then(self, next: Self) -> Self {
+        if next.k == 0 {
+            return Self::constant(next.at(0));
+        }
+        // next.k * clamp(v, lo, hi) + next.a is next.k * v + next.a clamped between where lo and
+        // hi go, and clamping to that range then next's is clamping to their overlap
+        let (p, q) = (next.k * self.lo + next.a, next.k * self.hi + next.a);
+        let (lo, hi) = (p.min(q), p.max(q));
+        if hi < next.lo {
+            return Self::constant(next.lo);
+        }
+        if lo > next.hi {
+            return Self::constant(next.hi);
+        }
+        Self {
+            k: self.k * next.k,
+            a: self.a * next.k + next.a,
+            lo: lo.max(next.lo),
+            hi: hi.min(next.hi),
+        }
+        .normalise()
+    }
+
+    // Only values from 0 to MAX_VALUE ever go in, so any Effect that agrees on those will do.
+    // Keeping |k| at most MAX_VALUE + 1 (and so |a| not much over its square) stops composing
+    // lots of them from overflowing.
+    fn normalise(self) -> Self {
+        let (first, last) = (self.at(0), self.at(MAX_VALUE));
+        if first == last {
+            return Self::constant(first);
+        }
+        let steep = MAX_VALUE + 1;
+        if self.k.abs() <= steep {
+            return self;
+        }
+        // Each step in x goes further than from lo to hi, so at most one x (x1, the first whose
+        // value differs from the value at 0) doesn't give lo or hi.  Any k this steep with the
+        // same value at x1 gives the same values everywhere.
+        let x1 = if self.k > 0 {
+            (self.lo - self.a).div_euclid(self.k) + 1
+        } else {
+            (self.a - self.hi).div_euclid(-self.k) + 1
+        }
+        .max(0);
+        let k = steep * self.k.signum();
+        Self {
+            k,
+            a: self.at(x1) - k * x1,
+            ..self
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct Instruction {
-    action: Action,
+    action: String,   // a phrase from the action table
     from: (u32, u32), // x,y
     to: (u32, u32),
 }
 
-impl From<&str> for Instruction {
-    fn from(s: &str) -> Self {
-        lazy_static! {
-            static ref RE: Regex =
-                Regex::new(r"^(turn on|toggle|turn off) (\d+),(\d+) through (\d+),(\d+)$").unwrap();
+// turn on 0,0 through 999,999
+impl Instruction {
+    fn parse(p: &mut Parser, actions: &ActionTable) -> Result<Self, ParseError> {
+        let phrases: Vec<(&str, usize)> = actions
+            .actions
+            .iter()
+            .enumerate()
+            .map(|(i, (phrase, _))| (phrase.as_str(), i))
+            .collect();
+        let action = actions.actions[p.keyword(&phrases)?].0.clone();
+        p.literal(" ")?;
+        let from = parse_point(p)?;
+        p.literal(" through ")?;
+        let start = p.position();
+        let to = parse_point(p)?;
+        // the corners have to be in order; everything else assumes from is the top left
+        if to.0 < from.0 || to.1 < from.1 {
+            return Err(p.error_at(start, "corners out of order"));
+        }
+        Ok(Self { action, from, to })
+    }
+}
+
+fn parse_point(p: &mut Parser) -> Result<(u32, u32), ParseError> {
+    let x = p.integer()?;
+    p.literal(",")?;
+    Ok((x, p.integer()?))
+}
+
+fn parse_input(input: &str, actions: &ActionTable) -> Vec<Instruction> {
+    parse::lines(input, |p| Instruction::parse(p, actions)).unwrap()
+}
+
+// A rectangle of lights, corners included
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Rect {
+    from: (u32, u32),
+    to: (u32, u32),
+}
+
+// The lights after some instructions.  Only the coordinates where instructions start and end
+// matter, so the grid is divided into cells at those coordinates and each cell holds one value for
+// all its lights.  Any light outside every instruction stays off.
+struct LightGrid {
+    actions: ActionTable,
+    // cell boundaries; cell (i, j) covers x from xs[i] up to xs[i + 1] and y likewise.  u64 so
+    // the boundary after u32::MAX fits.
+    xs: Vec<u64>,
+    ys: Vec<u64>,
+    x_map: HashMap<u64, usize>,
+    y_map: HashMap<u64, usize>,
+    // by x then y.  Shared with snapshots until one side changes.
+    cells: Rc<Vec<u32>>,
+    // instructions applied with undo information, latest first
+    history: Option<Rc<Step>>,
+    // for rectangle queries: prefix[i * (rows + 1) + j] is the total of cells (..i, ..j).  Built
+    // when first needed after a change.
+    prefix: OnceCell<Vec<u128>>,
+}
+
+impl LightGrid {
+    // a grid with room for these instructions, all lights off
+    fn new(instructions: &[Instruction], actions: &ActionTable) -> Self {
+        let (xs, ys) = build_axes(instructions);
+        let cells = vec![0; xs.len().saturating_sub(1) * ys.len().saturating_sub(1)];
+        Self {
+            actions: actions.clone(),
+            x_map: build_axes_map(&xs),
+            y_map: build_axes_map(&ys),
+            xs,
+            ys,
+            cells: Rc::new(cells),
+            history: None,
+            prefix: OnceCell::new(),
+        }
+    }
+
+    // without keeping anything for undo
+    fn from_instructions(instructions: &[Instruction], actions: &ActionTable) -> Self {
+        let mut grid = Self::new(instructions, actions);
+        for inst in instructions {
+            grid.update(inst, None);
+        }
+        grid
+    }
+
+    fn rows(&self) -> usize {
+        self.ys.len().saturating_sub(1)
+    }
+
+    // the cells an instruction covers: (x range, y range)
+    fn cell_ranges(&self, inst: &Instruction) -> (Range<usize>, Range<usize>) {
+        let x0 = self.x_map[&(inst.from.0 as u64)];
+        let x1 = self.x_map[&(inst.to.0 as u64 + 1)];
+        let y0 = self.y_map[&(inst.from.1 as u64)];
+        let y1 = self.y_map[&(inst.to.1 as u64 + 1)];
+        (x0..x1, y0..y1)
+    }
+
+    // The instruction must be one of those the grid was made for.  The old values of the cells it
+    // covers are added to old if given.
+    fn update(&mut self, inst: &Instruction, mut old: Option<&mut Vec<u32>>) {
+        let (xr, yr) = self.cell_ranges(inst);
+        let rows = self.rows();
+        let effect = self.actions.effect(&inst.action);
+        let cells = Rc::make_mut(&mut self.cells);
+        for x in xr {
+            for cell in &mut cells[x * rows + yr.start..x * rows + yr.end] {
+                if let Some(old) = old.as_mut() {
+                    old.push(*cell);
+                }
+                *cell = effect.apply(*cell);
+            }
+        }
+        self.prefix = OnceCell::new();
+    }
+
+    // number of lights on, or total brightness
+    fn total(&self) -> u128 {
+        let rows = self.rows();
+        let mut total = 0;
+        for (i, column) in self.cells.chunks(rows.max(1)).enumerate() {
+            for (j, &cell) in column.iter().enumerate() {
+                total += cell as u128 * self.area(i, j);
+            }
+        }
+        total
+    }
+
+    fn area(&self, i: usize, j: usize) -> u128 {
+        (self.xs[i + 1] - self.xs[i]) as u128 * (self.ys[j + 1] - self.ys[j]) as u128
+    }
+}
+
+// Queries
+#[allow(dead_code)]
+impl LightGrid {
+    // the cell a coordinate is in, if it's inside the grid
+    fn cell_index(axis: &[u64], c: u64) -> Option<usize> {
+        let i = axis.partition_point(|&b| b <= c);
+        (i > 0 && i < axis.len()).then(|| i - 1)
+    }
+
+    // whether the light is on (1 or 0), or its brightness
+    fn get(&self, x: u32, y: u32) -> u32 {
+        match (
+            Self::cell_index(&self.xs, x as u64),
+            Self::cell_index(&self.ys, y as u64),
+        ) {
+            (Some(i), Some(j)) => self.cells[i * self.rows() + j],
+            _ => 0,
+        }
+    }
+
+    fn prefix(&self) -> &[u128] {
+        self.prefix.get_or_init(|| {
+            let (columns, rows) = (self.xs.len().saturating_sub(1), self.rows());
+            let mut prefix = vec![0; (columns + 1) * (rows + 1)];
+            for i in 0..columns {
+                for j in 0..rows {
+                    prefix[(i + 1) * (rows + 1) + j + 1] = self.cells[i * rows + j] as u128
+                        * self.area(i, j)
+                        + prefix[i * (rows + 1) + j + 1]
+                        + prefix[(i + 1) * (rows + 1) + j]
+                        - prefix[i * (rows + 1) + j];
+                }
+            }
+            prefix
+        })
+    }
+
+    // total of the lights with coordinates below (x, y).  x and y can fall part way through a
+    // cell, which then counts in proportion.
+    fn total_below(&self, x: u64, y: u64) -> u128 {
+        let prefix = self.prefix();
+        let stride = self.rows() + 1;
+        let at = |i: usize, j: usize| prefix[i * stride + j];
+        // whole cells below x and y, and how far into the next cell each goes
+        let split = |axis: &[u64], c: u64| {
+            let i = axis.partition_point(|&b| b <= c).saturating_sub(1);
+            if axis.is_empty() || c <= axis[0] {
+                (0, 0)
+            } else if i + 1 >= axis.len() {
+                (axis.len() - 1, 0)
+            } else {
+                (i, c - axis[i])
+            }
+        };
+        let ((i, dx), (j, dy)) = (split(&self.xs, x), split(&self.ys, y));
+        let mut total = at(i, j);
+        if dx > 0 {
+            // part of column i, for all rows below j
+            let width = (self.xs[i + 1] - self.xs[i]) as u128;
+            total += (at(i + 1, j) - at(i, j)) / width * dx as u128;
+        }
+        if dy > 0 {
+            let height = (self.ys[j + 1] - self.ys[j]) as u128;
+            total += (at(i, j + 1) - at(i, j)) / height * dy as u128;
+        }
+        if dx > 0 && dy > 0 {
+            total += self.cells[i * self.rows() + j] as u128 * dx as u128 * dy as u128;
+        }
+        total
+    }
+
+    // number of lights on, or total brightness, in a rectangle
+    fn sum(&self, rect: Rect) -> u128 {
+        let (x0, y0) = (rect.from.0 as u64, rect.from.1 as u64);
+        let (x1, y1) = (rect.to.0 as u64 + 1, rect.to.1 as u64 + 1);
+        self.total_below(x1, y1) + self.total_below(x0, y0)
+            - self.total_below(x0, y1)
+            - self.total_below(x1, y0)
+    }
+
+    // Covers the grid with rectangles each of a single value.  Rectangles are grown from the top
+    // left: as far along x as the value stays the same, then along y as far as the whole width
+    // does, so none can be extended to the right or further down.
+    fn uniform_rects(&self) -> Vec<(Rect, u32)> {
+        let (columns, rows) = (self.xs.len().saturating_sub(1), self.rows());
+        let mut done = vec![false; self.cells.len()];
+        let mut rects = vec![];
+        for j in 0..rows {
+            for i in 0..columns {
+                if done[i * rows + j] {
+                    continue;
+                }
+                let value = self.cells[i * rows + j];
+                let same =
+                    |i: usize, j: usize| !done[i * rows + j] && self.cells[i * rows + j] == value;
+                let mut i1 = i + 1;
+                while i1 < columns && same(i1, j) {
+                    i1 += 1;
+                }
+                let mut j1 = j + 1;
+                while j1 < rows && (i..i1).all(|i| same(i, j1)) {
+                    j1 += 1;
+                }
+                for i in i..i1 {
+                    for j in j..j1 {
+                        done[i * rows + j] = true;
+                    }
+                }
+                rects.push((
+                    Rect {
+                        from: (self.xs[i] as u32, self.ys[j] as u32),
+                        to: ((self.xs[i1] - 1) as u32, (self.ys[j1] - 1) as u32),
+                    },
+                    value,
+                ));
+            }
+        }
+        rects
+    }
+}
+
+// One instruction in a grid's history.  The history is a linked list so snapshots can share it.
+struct Step {
+    inst: Instruction,
+    old: Vec<u32>,  // values of the cells it covered before, by x then y
+    applied: usize, // instructions applied up to and including this one
+    prev: Option<Rc<Step>>,
+}
+
+// dropping a long history one step at a time rather than recursively
+impl Drop for Step {
+    fn drop(&mut self) {
+        let mut prev = self.prev.take();
+        while let Some(step) = prev {
+            prev = match Rc::try_unwrap(step) {
+                Ok(mut step) => step.prev.take(),
+                Err(_) => None, // still used elsewhere
+            }
+        }
+    }
+}
+
+// The state of a grid, to go back to later
+struct Snapshot {
+    cells: Rc<Vec<u32>>,
+    history: Option<Rc<Step>>,
+}
+
+// Stepping through instructions one at a time, for debugging instruction lists
+#[allow(dead_code)]
+impl LightGrid {
+    // applies one instruction so that it can be undone
+    fn apply(&mut self, inst: &Instruction) {
+        let mut old = vec![];
+        self.update(inst, Some(&mut old));
+        self.history = Some(Rc::new(Step {
+            inst: inst.clone(),
+            old,
+            applied: self.applied() + 1,
+            prev: self.history.take(),
+        }));
+    }
+
+    fn applied(&self) -> usize {
+        self.history.as_ref().map_or(0, |step| step.applied)
+    }
+
+    // the instructions applied so far, in order
+    fn instructions(&self) -> Vec<Instruction> {
+        let mut instructions = vec![];
+        let mut step = &self.history;
+        while let Some(s) = step {
+            instructions.push(s.inst.clone());
+            step = &s.prev;
+        }
+        instructions.reverse();
+        instructions
+    }
+
+    // takes back the last instruction applied, returning it
+    fn undo(&mut self) -> Option<Instruction> {
+        let step = self.history.take()?;
+        let (xr, yr) = self.cell_ranges(&step.inst);
+        let rows = self.rows();
+        let cells = Rc::make_mut(&mut self.cells);
+        let mut old = step.old.iter();
+        for x in xr {
+            for cell in &mut cells[x * rows + yr.start..x * rows + yr.end] {
+                *cell = *old.next().unwrap();
+            }
+        }
+        self.prefix = OnceCell::new();
+        self.history = step.prev.clone();
+        Some(step.inst.clone())
+    }
+
+    // undo until only the first n instructions are applied
+    fn undo_to(&mut self, n: usize) {
+        while self.applied() > n {
+            self.undo();
+        }
+    }
+
+    // costs nothing until the grid next changes, when its cells are copied
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            cells: self.cells.clone(),
+            history: self.history.clone(),
+        }
+    }
+
+    // the snapshot must be of this grid
+    fn restore(&mut self, snapshot: &Snapshot) {
+        assert_eq!(self.cells.len(), snapshot.cells.len());
+        self.cells = snapshot.cells.clone();
+        self.history = snapshot.history.clone();
+        self.prefix = OnceCell::new();
+    }
+
+    // For each instruction applied, how much the total would go down if it were left out (so
+    // negative if the total would go up).  Instructions giving 0 made no difference to the answer.
+    fn answer_changes(&self) -> Vec<i128> {
+        let instructions = self.instructions();
+        let ranges: Vec<_> = instructions.iter().map(|i| self.cell_ranges(i)).collect();
+        let effects: Vec<_> = instructions
+            .iter()
+            .map(|i| self.actions.effect(&i.action))
+            .collect();
+        let mut changes = vec![0; instructions.len()];
+        let (columns, rows) = (self.xs.len().saturating_sub(1), self.rows());
+        let mut values = vec![];
+        for i in 0..columns {
+            let in_column: Vec<usize> = (0..instructions.len())
+                .filter(|&k| ranges[k].0.contains(&i))
+                .collect();
+            for j in 0..rows {
+                let covering: Vec<usize> = in_column
+                    .iter()
+                    .copied()
+                    .filter(|&k| ranges[k].1.contains(&j))
+                    .collect();
+                // value after each of the instructions covering this cell
+                values.clear();
+                values.push(0);
+                for &k in &covering {
+                    values.push(effects[k].apply(*values.last().unwrap()));
+                }
+                let last = *values.last().unwrap();
+                for (n, &k) in covering.iter().enumerate() {
+                    // skip instruction n, carrying on from the value before it
+                    let without = covering[n + 1..]
+                        .iter()
+                        .fold(values[n], |v, &k| effects[k].apply(v));
+                    changes[k] += (last as i128 - without as i128) * self.area(i, j) as i128;
+                }
+            }
+        }
+        changes
+    }
+}
+
+// Pictures of the lights: one greyscale pixel per light, brighter for brighter lights.  Pixel
+// (0, 0) is light 0,0.
+#[allow(dead_code)]
+impl LightGrid {
+    // from 0,0 to the furthest light any instruction reaches, if there are any
+    fn extent(&self) -> Option<Rect> {
+        let (x, y) = (*self.xs.last()?, *self.ys.last()?);
+        Some(Rect {
+            from: (0, 0),
+            to: ((x - 1) as u32, (y - 1) as u32),
+        })
+    }
+
+    fn max_value(&self) -> u32 {
+        self.cells.iter().copied().max().unwrap_or(0)
+    }
+
+    // lights with value max or more are white
+    fn render(&self, area: Rect, max: u32) -> Image {
+        let width = (area.to.0 - area.from.0) as usize + 1;
+        let height = (area.to.1 - area.from.1) as usize + 1;
+        let mut image = Image::new(width, height, image::grey(0));
+        // the cell each column and row of pixels is in
+        let columns: Vec<_> = (0..width)
+            .map(|x| Self::cell_index(&self.xs, area.from.0 as u64 + x as u64))
+            .collect();
+        let rows: Vec<_> = (0..height)
+            .map(|y| Self::cell_index(&self.ys, area.from.1 as u64 + y as u64))
+            .collect();
+        for (y, j) in rows.iter().enumerate() {
+            for (x, i) in columns.iter().enumerate() {
+                if let (Some(i), Some(j)) = (i, j) {
+                    let max = max.max(1) as u64;
+                    let value = (self.cells[i * self.rows() + j] as u64).min(max);
+                    image.set(x, y, image::grey((value * 255 / max) as u8));
+                }
+            }
+        }
+        image
+    }
+}
+
+// Writes frame0000.pgm, frame0001.pgm, ... to dir: the lights before any instructions, then after
+// every `every` instructions, and after the last one.  Every frame covers the same area and has
+// the same brightness scale, so they can be played as an animation.  Returns the files written.
+#[allow(dead_code)]
+fn write_frames(
+    instructions: &[Instruction],
+    actions: &ActionTable,
+    every: usize,
+    dir: &Path,
+) -> io::Result<Vec<PathBuf>> {
+    assert!(every > 0);
+    let mut grid = LightGrid::new(instructions, actions);
+    let Some(area) = grid.extent() else {
+        return Ok(vec![]);
+    };
+    let is_frame = |n: usize| n.is_multiple_of(every) || n == instructions.len();
+    // the brightest any frame gets, for the scale
+    let mut max = 0;
+    for (n, inst) in instructions.iter().enumerate() {
+        grid.update(inst, None);
+        if is_frame(n + 1) {
+            max = max.max(grid.max_value());
+        }
+    }
+
+    let mut grid = LightGrid::new(instructions, actions);
+    let mut paths = vec![];
+    for n in 0..=instructions.len() {
+        if n > 0 {
+            grid.update(&instructions[n - 1], None);
+        }
+        if is_frame(n) {
+            let path = dir.join(format!("frame{:04}.pgm", paths.len()));
+            grid.render(area, max).save_pgm(&path)?;
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+// A solver for lots of instructions over the whole u32 range, where the grid above would have too
+// many cells.  It sweeps across x, stopping wherever an instruction starts or ends; the lights in
+// each slab between stops are the same all the way across, so only one column needs working out.
+// That comes from a segment tree over time kept from one slab to the next.  Each leaf is an
+// instruction's Effect over its y range while the sweep is inside its x range (and the identity
+// otherwise), and each node the composition of its leaves in time order, as a function of y: the
+// pieces of the column where that's the same.  When an instruction starts or ends only the nodes
+// above its leaf are worked out again, and the root takes each piece of the column from off to its
+// final value.
+//
+// A node has at most two pieces for each instruction under it that the slab crosses, and pieces
+// next to each other with the same Effect are joined.  So where the slab crosses a instructions,
+// one starting or ending costs O(a) if they're spread out in time and O(a log(n / a)) at worst:
+// O(n^2) in all when every instruction crosses most of the others, against O(n^3) time and O(n^2)
+// memory for the grid.  30000 random instructions over the whole range take under a second on and
+// off, where most pieces join up, and about half a minute for brightness (see bench_sweep).
+
+// Pieces of a column of lights, each an Effect from a y to the next piece's y (or the bottom of the
+// grid, for the last).  The first starts at 0.
+type Column = Vec<(u64, Effect)>;
+
+// the bottom of the grid, for a column
+const COLUMN_END: u64 = 1 << 32;
+
+// Calls f with where each piece of a and b together starts, and a's and b's Effects there
+fn pieces(a: &Column, b: &Column, mut f: impl FnMut(u64, Effect, Effect)) {
+    let (mut i, mut j) = (0, 0);
+    loop {
+        f(a[i].0.max(b[j].0), a[i].1, b[j].1);
+        // on to whichever piece starts next, or both
+        match (a.get(i + 1), b.get(j + 1)) {
+            (Some(&(ya, _)), Some(&(yb, _))) => {
+                i += (ya <= yb) as usize;
+                j += (yb <= ya) as usize;
+            }
+            (Some(_), None) => i += 1,
+            (None, Some(_)) => j += 1,
+            (None, None) => break,
         }
-        let m = RE.captures(s).unwrap();
+    }
+}
+
+// Composition of a list of columns in order, where each can be changed
+struct TimeTree {
+    size: usize,        // leaves, rounded up to a power of 2
+    nodes: Vec<Column>, // node i has children 2i and 2i + 1; leaves start at size
+    stale: Vec<bool>,   // nodes with a leaf under them changed since they were worked out
+}
+
+impl TimeTree {
+    fn new(n: usize) -> Self {
+        // at least 2, so the root isn't a leaf
+        let size = n.next_power_of_two().max(2);
         Self {
-            action: Action::from(&m[1]),
-            from: (m[2].parse().unwrap(), m[3].parse().unwrap()),
-            to: (m[4].parse().unwrap(), m[5].parse().unwrap()),
+            size,
+            nodes: vec![vec![(0, Effect::IDENTITY)]; 2 * size],
+            stale: vec![false; 2 * size],
         }
     }
+
+    fn set(&mut self, t: usize, column: Column) {
+        let mut i = self.size + t;
+        self.nodes[i] = column;
+        i /= 2;
+        while i > 0 && !self.stale[i] {
+            self.stale[i] = true;
+            i /= 2;
+        }
+    }
+
+    // The total of the lights in the column, after every leaf's Effect.  Only the values are
+    // needed here, so the root's own pieces are never worked out.
+    fn lights(&mut self) -> u128 {
+        self.refresh(2);
+        self.refresh(3);
+        let mut lights = 0;
+        let mut last = (0, 0); // where the last piece started, and its lights' value
+        pieces(&self.nodes[2], &self.nodes[3], |y, a, b| {
+            lights += last.1 as u128 * (y - last.0) as u128;
+            last = (y, b.apply(a.apply(0)));
+        });
+        lights + last.1 as u128 * (COLUMN_END - last.0) as u128
+    }
+
+    fn refresh(&mut self, i: usize) {
+        if !self.stale[i] {
+            return;
+        }
+        self.stale[i] = false;
+        self.refresh(2 * i);
+        self.refresh(2 * i + 1);
+        // the composition, joining pieces next to each other that come out the same
+        let mut column = std::mem::take(&mut self.nodes[i]);
+        column.clear();
+        pieces(&self.nodes[2 * i], &self.nodes[2 * i + 1], |y, a, b| {
+            let effect = a.then(b);
+            if column.last().is_none_or(|&(_, e)| e != effect) {
+                column.push((y, effect));
+            }
+        });
+        self.nodes[i] = column;
+    }
 }
 
-fn parse_input(input: &str) -> Vec<Instruction> {
-    input.lines().map(Instruction::from).collect()
+#[allow(dead_code)]
+fn sweep_total(instructions: &[Instruction], actions: &ActionTable) -> u128 {
+    let effects: Vec<_> = instructions
+        .iter()
+        .map(|i| actions.effect(&i.action))
+        .collect();
+    // (x, instruction, starting) sorted by x
+    let mut x_events: Vec<(u64, usize, bool)> = instructions
+        .iter()
+        .enumerate()
+        .flat_map(|(t, i)| [(i.from.0 as u64, t, true), (i.to.0 as u64 + 1, t, false)])
+        .collect();
+    x_events.sort_unstable();
+
+    let mut tree = TimeTree::new(instructions.len());
+    let mut total = 0;
+    for (k, &(x, t, starting)) in x_events.iter().enumerate() {
+        let mut column = vec![(0, Effect::IDENTITY)];
+        if starting {
+            let inst = &instructions[t];
+            let (y0, y1) = (inst.from.1 as u64, inst.to.1 as u64 + 1);
+            if y0 == 0 {
+                column.clear();
+            }
+            column.push((y0, effects[t]));
+            if y1 < COLUMN_END {
+                column.push((y1, Effect::IDENTITY));
+            }
+        }
+        tree.set(t, column);
+        let width = match x_events.get(k + 1) {
+            Some(&(next, _, _)) if next > x => next - x,
+            _ => continue, // more events here, or none left
+        };
+
+        total += tree.lights() * width as u128;
+    }
+    total
 }
 
-fn build_axes(instructions: &[Instruction]) -> (Vec<u32>, Vec<u32>) {
+fn build_axes(instructions: &[Instruction]) -> (Vec<u64>, Vec<u64>) {
     let mut x = HashSet::new();
     let mut y = HashSet::new();
     for inst in instructions {
-        x.insert(inst.from.0);
-        x.insert(inst.to.0 + 1);
-        y.insert(inst.from.1);
-        y.insert(inst.to.1 + 1);
+        x.insert(inst.from.0 as u64);
+        x.insert(inst.to.0 as u64 + 1);
+        y.insert(inst.from.1 as u64);
+        y.insert(inst.to.1 as u64 + 1);
     }
     let mut x: Vec<_> = x.into_iter().collect();
     let mut y: Vec<_> = y.into_iter().collect();
@@ -75,93 +875,395 @@ fn build_axes(instructions: &[Instruction]) -> (Vec<u32>, Vec<u32>) {
     (x, y)
 }
 
-fn build_axes_map(axes: &[u32]) -> HashMap<u32, usize> {
+fn build_axes_map(axes: &[u64]) -> HashMap<u64, usize> {
     HashMap::from_iter(axes.iter().enumerate().map(|(i, c)| (*c, i)))
 }
 
-#[allow(clippy::needless_range_loop)]
-fn part1(instructions: &[Instruction]) -> usize {
-    let (x_axes, y_axes) = build_axes(instructions);
-    let x_map = build_axes_map(&x_axes);
-    let y_map = build_axes_map(&y_axes);
+// the part 1 and part 2 tables
+#[cfg(test)]
+fn builtin_actions() -> [ActionTable; 2] {
+    [PART1_ACTIONS, PART2_ACTIONS].map(|s| ActionTable::parse(s).unwrap())
+}
 
-    let mut grid = vec![vec![false; y_axes.len()]; x_axes.len()];
+#[test]
+fn test() {
+    assert_eq!("1000000", run("turn on 0,0 through 999,999\n", Part::One));
+    assert_eq!("1000", run("toggle 0,0 through 999,0\n", Part::One));
+    assert_eq!("0", run("turn off 499,499 through 500,500\n", Part::One));
 
-    for inst in instructions {
-        let x0 = x_map[&inst.from.0];
-        let x1 = x_map[&(inst.to.0 + 1)];
-        let y0 = y_map[&inst.from.1];
-        let y1 = y_map[&(inst.to.1 + 1)];
-        for x in x0..x1 {
-            for y in y0..y1 {
-                grid[x][y] = match inst.action {
-                    Action::TurnOn => true,
-                    Action::Toggle => !grid[x][y],
-                    Action::TurnOff => false,
-                }
-            }
+    assert_eq!("1", run("turn on 0,0 through 0,0\n", Part::Two));
+    assert_eq!("2000000", run("toggle 0,0 through 999,999\n", Part::Two));
+}
+
+#[test]
+fn test_actions() {
+    use crate::rng::Rng;
+
+    let actions = ActionTable::parse(
+        "# a dimmer\n\
+         \n\
+         set: set 5\n\
+         brighten: add 3\n\
+         double: multiply 2\n\
+         limit: clamp 2..10\n\
+         dim: add -4, clamp 1..\n\
+         invert: invert 12\n",
+    )
+    .unwrap();
+    let after = |phrase: &str, values: [u32; 4]| values.map(|v| actions.effect(phrase).apply(v));
+    assert_eq!([5, 5, 5, 5], after("set", [0, 1, 7, 20]));
+    assert_eq!([3, 4, 10, 23], after("brighten", [0, 1, 7, 20]));
+    assert_eq!([0, 2, 14, 40], after("double", [0, 1, 7, 20]));
+    assert_eq!([2, 2, 7, 10], after("limit", [0, 1, 7, 20]));
+    assert_eq!([1, 1, 3, 16], after("dim", [0, 1, 7, 20]));
+    assert_eq!([12, 11, 5, 0], after("invert", [0, 1, 7, 20]));
+    assert_eq!(
+        [u32::MAX, u32::MAX, 4, u32::MAX],
+        after("brighten", [u32::MAX - 1, u32::MAX, 1, u32::MAX - 3])
+    );
+
+    let instructions = parse_input(
+        "set 0,0 through 1,0\n\
+         double 1,0 through 2,0\n\
+         invert 0,0 through 2,0\n",
+        &actions,
+    );
+    assert_eq!(
+        7 + 2 + 12,
+        LightGrid::from_instructions(&instructions, &actions).total()
+    );
+
+    let error = |s| ActionTable::parse(s).err().map(|e| e.to_string());
+    assert_eq!(
+        Some("line 2, column 1: action \"on\" is already defined".to_string()),
+        error("on: set 1\non: add 1\n")
+    );
+    assert_eq!(
+        Some("line 1, column 12: the range is empty".to_string()),
+        error("off: clamp 3..2\n")
+    );
+    assert_eq!(
+        Some(
+            "line 1, column 5: expected one of \"set \", \"add \", \"multiply \", \"invert \", \"clamp \""
+                .to_string()
+        ),
+        error("on: halve\n")
+    );
+    assert_eq!(Some("no actions".to_string()), error("# nothing\n"));
+
+    // composing steps agrees with doing them one at a time, however steep they get
+    let steps = [
+        "set 3",
+        "add 5",
+        "add -7",
+        "multiply 3",
+        "multiply -2",
+        "multiply 0",
+        "invert 9",
+        "invert 4294967295",
+        "multiply 65537",
+        "add 4294967295",
+        "add -4294967295",
+        "clamp 4..9",
+        "clamp 100..",
+        "clamp ..20",
+    ];
+    let effects: Vec<Effect> = steps
+        .iter()
+        .map(|s| parse_step(&mut Parser::new(s)).unwrap())
+        .collect();
+    let mut rng = Rng::new(7);
+    let mut next = |m: usize| rng.below(m);
+    for _ in 0..2000 {
+        let sequence: Vec<Effect> = (0..next(40))
+            .map(|_| effects[next(effects.len())])
+            .collect();
+        let composed = sequence.iter().fold(Effect::IDENTITY, |f, &g| f.then(g));
+        assert!(composed.k.abs() <= MAX_VALUE + 1);
+        for x in [0, 1, 2, 5, 99, 65536, 1 << 31, u32::MAX - 1, u32::MAX] {
+            let one_at_a_time = sequence.iter().fold(x, |v, g| g.apply(v));
+            assert_eq!(one_at_a_time, composed.apply(x));
         }
     }
+}
 
-    let mut count = 0;
-    for x in 0..(x_axes.len() - 1) {
-        for y in 0..(y_axes.len() - 1) {
-            if grid[x][y] {
-                count += (x_axes[x + 1] - x_axes[x]) as usize * (y_axes[y + 1] - y_axes[y]) as usize
-            }
-        }
+#[test]
+fn test_grid() {
+    let [on_off, brightness] = builtin_actions();
+    let instructions = parse_input(
+        "turn on 0,0 through 9,9\n\
+         toggle 5,5 through 14,14\n\
+         turn off 2,3 through 3,4\n\
+         turn on 4000000000,10 through 4294967295,10\n",
+        &on_off,
+    );
+    let grid = LightGrid::from_instructions(&instructions, &on_off);
+    assert_eq!(
+        (1, 0, 1, 0),
+        (
+            grid.get(0, 0),
+            grid.get(5, 5),
+            grid.get(14, 14),
+            grid.get(15, 15)
+        )
+    );
+    assert_eq!(
+        (0, 1, 0),
+        (
+            grid.get(2, 4),
+            grid.get(4294967295, 10),
+            grid.get(3999999999, 10)
+        )
+    );
+    assert_eq!(100 - 25 + 75 - 4 + 294967296, grid.total());
+    let rect = |x0, y0, x1, y1| Rect {
+        from: (x0, y0),
+        to: (x1, y1),
+    };
+    assert_eq!(grid.total(), grid.sum(rect(0, 0, u32::MAX, u32::MAX)));
+    assert_eq!(1, grid.sum(rect(5, 4, 5, 4)));
+    assert_eq!(0, grid.sum(rect(5, 5, 9, 9)));
+    assert_eq!(9 - 4, grid.sum(rect(1, 2, 3, 4)));
+    assert_eq!(2 + 9, grid.sum(rect(8, 8, 12, 10)));
+    assert_eq!(101, grid.sum(rect(4294967195, 0, 4294967295, 20)));
+
+    // every light is in exactly one uniform rectangle
+    let rects = grid.uniform_rects();
+    let area = |r: &Rect| (r.to.0 - r.from.0 + 1) as u128 * (r.to.1 - r.from.1 + 1) as u128;
+    for (r, value) in &rects {
+        assert_eq!(*value as u128 * area(r), grid.sum(*r));
+        assert_eq!(*value, grid.get(r.from.0, r.from.1));
     }
-    count
+    assert_eq!(
+        grid.total(),
+        rects
+            .iter()
+            .map(|(r, v)| *v as u128 * area(r))
+            .sum::<u128>()
+    );
+    assert_eq!(
+        4294967296 * 15,
+        rects.iter().map(|(r, _)| area(r)).sum::<u128>()
+    );
+    assert!(rects.contains(&(rect(2, 3, 3, 4), 0)));
+
+    let grid = LightGrid::from_instructions(&instructions, &brightness);
+    assert_eq!(3, grid.get(9, 9));
+    assert_eq!(4 * 3 + 5 * 2, grid.sum(rect(8, 8, 10, 10)));
+
+    let error = |input| {
+        parse::lines(input, |p| Instruction::parse(p, &on_off))
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        "line 2, column 21: corners out of order",
+        error("turn on 0,0 through 1,1\nturn on 0,5 through 3,2\n")
+    );
+    assert_eq!(
+        "line 1, column 20: corners out of order",
+        error("toggle 5,0 through 2,0\n")
+    );
 }
 
-#[allow(clippy::needless_range_loop)]
-fn part2(instructions: &[Instruction]) -> usize {
-    let (x_axes, y_axes) = build_axes(instructions);
-    let x_map = build_axes_map(&x_axes);
-    let y_map = build_axes_map(&y_axes);
+#[test]
+fn test_undo() {
+    let instructions = parse_input(
+        "turn on 0,0 through 9,9\n\
+         toggle 5,5 through 14,14\n\
+         turn off 2,3 through 3,4\n\
+         turn off 20,20 through 29,29\n\
+         toggle 0,0 through 29,29\n\
+         turn on 0,0 through 0,0\n",
+        &builtin_actions()[0],
+    );
+    for (part, actions) in builtin_actions().iter().enumerate() {
+        let mut grid = LightGrid::new(&instructions, actions);
+        let mut snapshots = vec![grid.snapshot()];
+        for (n, inst) in instructions.iter().enumerate() {
+            grid.apply(inst);
+            snapshots.push(grid.snapshot());
+            let expected = LightGrid::from_instructions(&instructions[..=n], actions).total();
+            assert_eq!((n + 1, expected), (grid.applied(), grid.total()));
+        }
 
-    let mut grid = vec![vec![0; y_axes.len()]; x_axes.len()];
+        grid.undo_to(2);
+        assert_eq!(2, grid.applied());
+        assert_eq!(snapshots[2].cells, grid.cells);
+        assert_eq!(
+            LightGrid::from_instructions(&instructions[..2], actions).total(),
+            grid.total()
+        );
+        grid.apply(&instructions[4]);
+        grid.restore(&snapshots[5]);
+        assert_eq!(5, grid.applied());
+        assert_eq!(snapshots[5].cells, grid.cells);
+        grid.undo();
+        grid.undo();
+        assert_eq!(snapshots[3].cells, grid.cells);
+        // the snapshots weren't affected
+        assert_eq!(
+            LightGrid::from_instructions(&instructions, actions).cells,
+            snapshots[6].cells
+        );
 
-    for inst in instructions {
-        let x0 = x_map[&inst.from.0];
-        let x1 = x_map[&(inst.to.0 + 1)];
-        let y0 = y_map[&inst.from.1];
-        let y1 = y_map[&(inst.to.1 + 1)];
-        for x in x0..x1 {
-            for y in y0..y1 {
-                match inst.action {
-                    Action::TurnOn => grid[x][y] += 1,
-                    Action::Toggle => grid[x][y] += 2,
-                    Action::TurnOff => {
-                        if grid[x][y] > 0 {
-                            grid[x][y] -= 1;
-                        }
-                    }
-                }
+        // check against leaving each instruction out
+        grid.restore(&snapshots[6]);
+        let changes = grid.answer_changes();
+        for (n, change) in changes.iter().enumerate() {
+            let mut without = instructions.clone();
+            without.remove(n);
+            let mut other = LightGrid::new(&instructions, actions);
+            for inst in &without {
+                other.apply(inst);
             }
+            assert_eq!(*change, grid.total() as i128 - other.total() as i128);
         }
-    }
-
-    let mut count = 0;
-    for x in 0..(x_axes.len() - 1) {
-        for y in 0..(y_axes.len() - 1) {
-            count += (x_axes[x + 1] - x_axes[x]) as usize
-                * (y_axes[y + 1] - y_axes[y]) as usize
-                * grid[x][y]
+        if part == 0 {
+            // turning off lights that were already off makes no difference
+            assert_eq!(vec![-45, -50, 4, 0, 609, 1], changes);
         }
     }
-    count
 }
 
 #[test]
-fn test() {
+fn test_render() {
+    let instructions = parse_input(
+        "turn on 1,0 through 2,1\n\
+         toggle 2,1 through 3,2\n\
+         turn on 2,1 through 2,1\n",
+        &builtin_actions()[0],
+    );
+    let [on_off, brightness] = builtin_actions();
+    let grid = LightGrid::from_instructions(&instructions, &brightness);
+    let area = grid.extent().unwrap();
+    assert_eq!(((0, 0), (3, 2)), (area.from, area.to));
+    let image = grid.render(area, grid.max_value());
+    assert_eq!((4, 3), (image.width, image.height));
+    let pixels: Vec<u8> = (0..3)
+        .flat_map(|y| (0..4).map(move |x| (x, y)))
+        .map(|(x, y)| image.get(x, y)[0])
+        .collect();
+    assert_eq!(vec![0, 63, 63, 0, 0, 63, 255, 127, 0, 0, 127, 127], pixels);
+
+    // values near the top of the range
+    let bright = ActionTable::parse("turn on: set 4000000000\nturn off: set 2000000000\n").unwrap();
+    let grid = LightGrid::from_instructions(
+        &parse_input(
+            "turn on 0,0 through 1,1\nturn off 1,1 through 1,1\n",
+            &bright,
+        ),
+        &bright,
+    );
+    let image = grid.render(grid.extent().unwrap(), grid.max_value());
+    assert_eq!([255, 127], [image.get(0, 0)[0], image.get(1, 1)[0]]);
+    let image = grid.render(grid.extent().unwrap(), u32::MAX);
+    assert_eq!(237, image.get(0, 0)[0]);
+
+    let dir = std::env::temp_dir().join(format!("day6-frames-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let paths = write_frames(&instructions, &on_off, 2, &dir).unwrap();
+    assert_eq!(3, paths.len());
+    let last = std::fs::read(&paths[2]).unwrap();
+    assert_eq!(
+        b"P5\n4 3\n255\n\0\xff\xff\0\0\xff\xff\xff\0\0\xff\xff",
+        &last[..]
+    );
+    let first = std::fs::read(&paths[0]).unwrap();
+    assert!(first[11..].iter().all(|&p| p == 0));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Random instructions in a small area so they overlap a lot
+#[cfg(test)]
+fn random_instructions(n: usize, size: u32, seed: u64) -> Vec<Instruction> {
+    use crate::rng::Rng;
+
+    let mut rng = Rng::new(seed);
+    let mut next = |m: u32| rng.below(m as usize) as u32;
+    (0..n)
+        .map(|_| {
+            let action = ["turn on", "toggle", "turn off"][next(3) as usize].to_string();
+            let (x0, y0) = (next(size), next(size));
+            let to = (
+                x0.saturating_add(next(size / 2)).min(size - 1),
+                y0.saturating_add(next(size / 2)).min(size - 1),
+            );
+            Instruction {
+                action,
+                from: (x0, y0),
+                to,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_sweep() {
+    let [on_off, brightness] = builtin_actions();
+    let other = ActionTable::parse(
+        "turn on: multiply 3, add 1\n\
+         toggle: invert 100, multiply 2\n\
+         turn off: clamp 5..50\n",
+    )
+    .unwrap();
+    let tables = [on_off, brightness, other];
+    for (n, size, seed) in [
+        (1, 10, 1),
+        (10, 10, 2),
+        (50, 1000, 3),
+        (200, 50, 4),
+        (300, 1000, 5),
+    ] {
+        let instructions = random_instructions(n, size, seed);
+        for actions in &tables {
+            assert_eq!(
+                LightGrid::from_instructions(&instructions, actions).total(),
+                sweep_total(&instructions, actions)
+            );
+        }
+    }
+    let [on_off, brightness] = builtin_actions();
+    let edges = parse_input(
+        "toggle 4294967295,0 through 4294967295,4294967295\n",
+        &on_off,
+    );
+    assert_eq!(1 << 32, sweep_total(&edges, &on_off));
+    assert_eq!(0, sweep_total(&[], &brightness));
+
+    // a rectangle given right to left would leave its effect on everything to its right, so it
+    // never gets that far
+    let backwards = "turn on 5,0 through 2,0\nturn on 100,0 through 100,0\n";
+    assert!(parse::lines(backwards, |p| Instruction::parse(p, &on_off)).is_err());
+    let forwards = parse_input(
+        "turn on 2,0 through 5,0\nturn on 100,0 through 100,0\n",
+        &on_off,
+    );
+    assert_eq!(5, sweep_total(&forwards, &on_off));
     assert_eq!(
-        1000 * 1000,
-        part1(&parse_input("turn on 0,0 through 999,999\n"))
+        LightGrid::from_instructions(&forwards, &on_off).total(),
+        sweep_total(&forwards, &on_off)
     );
-    assert_eq!(1000, part1(&parse_input("toggle 0,0 through 999,0\n")));
-    assert_eq!(0, part1(&parse_input("turn off 499,499 through 500,500\n")));
+}
 
-    assert_eq!(1, part2(&parse_input("turn on 0,0 through 0,0\n")));
-    assert_eq!(2000000, part2(&parse_input("toggle 0,0 through 999,999\n")));
+// cargo test --release bench_sweep -- --ignored --nocapture
+#[test]
+#[ignore]
+fn bench_sweep() {
+    use std::time::Instant;
+
+    for n in [300, 3000, 30000] {
+        let instructions = random_instructions(n, u32::MAX, 42);
+        for (part, actions) in builtin_actions().iter().enumerate() {
+            let t0 = Instant::now();
+            let total = sweep_total(&instructions, actions);
+            println!(
+                "{:5} instructions, part {}: {} in {:.3}s",
+                n,
+                part + 1,
+                total,
+                t0.elapsed().as_secs_f64()
+            );
+        }
+    }
 }
@@ -1,100 +1,837 @@
+use crate::parse::{self, ParseError, Parser};
+use crate::sat::{Lit, Solver};
 use crate::Part;
-use lazy_static::lazy_static;
-use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
 
 pub fn run(input: &str, part: Part) -> String {
-    let mut wires = parse_input(input);
+    let circuit = parse_input(input).unwrap();
+    let mut program = Program::compile(&circuit).unwrap_or_else(|e| panic!("{}", e));
+    let a = program.id("a").expect("no wire a");
+    let signal = program.evaluate()[a];
     format!(
         "{}",
         match part {
-            Part::One => measure_a(&wires),
+            Part::One => signal,
             Part::Two => {
-                wires.insert("b".to_string(), Wire::Signal(measure_a(&wires)));
-                measure_a(&wires)
+                program.set_signal(program.id("b").expect("no wire b"), signal);
+                program.evaluate()[a]
             }
         }
     )
 }
 
-fn measure_a(wires: &HashMap<String, Wire>) -> u16 {
-    measure(wires, "a", &mut HashMap::new())
+#[derive(Clone, Debug, PartialEq)]
+enum Operand {
+    Wire(String),
+    Signal(u16),
+}
+
+#[derive(Clone, Debug)]
+enum Wire {
+    Direct(Operand),
+    And(Operand, Operand),
+    Or(Operand, Operand),
+    Lshift(Operand, u16),
+    Rshift(Operand, u16),
+    Not(Operand),
 }
 
-fn measure(wires: &HashMap<String, Wire>, target: &str, cache: &mut HashMap<String, u16>) -> u16 {
-    if let Some(v) = cache.get(target) {
-        return *v;
+impl Wire {
+    fn inputs(&self) -> Vec<&Operand> {
+        match self {
+            Wire::Direct(a) | Wire::Lshift(a, _) | Wire::Rshift(a, _) | Wire::Not(a) => vec![a],
+            Wire::And(a, b) | Wire::Or(a, b) => vec![a, b],
+        }
+    }
+
+    fn map_inputs(&self, f: impl Fn(&Operand) -> Operand) -> Wire {
+        match self {
+            Wire::Direct(a) => Wire::Direct(f(a)),
+            Wire::And(a, b) => Wire::And(f(a), f(b)),
+            Wire::Or(a, b) => Wire::Or(f(a), f(b)),
+            Wire::Lshift(a, v) => Wire::Lshift(f(a), *v),
+            Wire::Rshift(a, v) => Wire::Rshift(f(a), *v),
+            Wire::Not(a) => Wire::Not(f(a)),
+        }
     }
 
-    let signal = if let Some(wire) = wires.get(target) {
-        match wire {
-            Wire::Signal(v) => *v,
-            Wire::Direct(a) => measure(wires, a, cache),
-            Wire::And(a, b) => measure(wires, a, cache) & measure(wires, b, cache),
-            Wire::Or(a, b) => measure(wires, a, cache) | measure(wires, b, cache),
-            Wire::Lshift(a, v) => measure(wires, a, cache) << v,
-            Wire::Rshift(a, v) => measure(wires, a, cache) >> v,
-            Wire::Not(a) => !measure(wires, a, cache),
+    // given the signals on its inputs, in order.  Shifting by 16 or more leaves nothing.
+    fn signal(&self, mut get: impl FnMut(&Operand) -> u16) -> u16 {
+        match self {
+            Wire::Direct(a) => get(a),
+            Wire::And(a, b) => get(a) & get(b),
+            Wire::Or(a, b) => get(a) | get(b),
+            Wire::Lshift(a, v) => get(a).checked_shl(*v as u32).unwrap_or(0),
+            Wire::Rshift(a, v) => get(a).checked_shr(*v as u32).unwrap_or(0),
+            Wire::Not(a) => !get(a),
         }
-    } else {
-        // immediate value instead of a wire name
-        target.parse::<u16>().unwrap()
-    };
-    cache.insert(target.to_owned(), signal);
-    signal
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::Wire(w) => write!(f, "{}", w),
+            Operand::Signal(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+// the part before " -> "
+impl fmt::Display for Wire {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Wire::Direct(a) => write!(f, "{}", a),
+            Wire::And(a, b) => write!(f, "{} AND {}", a, b),
+            Wire::Or(a, b) => write!(f, "{} OR {}", a, b),
+            Wire::Lshift(a, v) => write!(f, "{} LSHIFT {}", a, v),
+            Wire::Rshift(a, v) => write!(f, "{} RSHIFT {}", a, v),
+            Wire::Not(a) => write!(f, "NOT {}", a),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum CircuitError {
+    // wires used but never defined, with the line using each (or 0 if they were asked for by
+    // name rather than used in the input)
+    Undefined(Vec<(String, usize)>),
+    // wires each fed by the one before, and the first by the last
+    Cycle(Vec<String>),
+    // a target and a signal that no signals on the free wires put on it
+    Unreachable(String, u16),
+    // a target and the free wires it doesn't depend on
+    Unused(String, Vec<String>),
+}
+
+impl fmt::Display for CircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CircuitError::Undefined(uses) => {
+                let uses: Vec<_> = uses
+                    .iter()
+                    .map(|(wire, line)| match line {
+                        0 => wire.clone(),
+                        _ => format!("{} (line {})", wire, line),
+                    })
+                    .collect();
+                write!(f, "undefined wires: {}", uses.join(", "))
+            }
+            CircuitError::Cycle(wires) => {
+                write!(f, "wires in a loop: {} -> {}", wires.join(" -> "), wires[0])
+            }
+            CircuitError::Unreachable(target, signal) => {
+                write!(
+                    f,
+                    "no signals on the free wires put {} on {}",
+                    signal, target
+                )
+            }
+            CircuitError::Unused(target, wires) => {
+                write!(
+                    f,
+                    "{} doesn't depend on free wires: {}",
+                    target,
+                    wires.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CircuitError {}
+
+struct Circuit {
+    wires: HashMap<String, (Wire, usize)>, // by name, with the line each is defined on
+}
+
+impl Circuit {
+    // every wire's signal, by name
+    #[allow(dead_code)]
+    fn evaluate(&self) -> Result<HashMap<String, u16>, CircuitError> {
+        let program = Program::compile(self)?;
+        let signals = program.evaluate();
+        Ok(program
+            .ids
+            .iter()
+            .map(|(name, &id)| (name.clone(), signals[id]))
+            .collect())
+    }
+
+    // an error naming any of names that isn't a wire
+    fn check_defined(&self, names: &[&str]) -> Result<(), CircuitError> {
+        let mut undefined: Vec<(String, usize)> = names
+            .iter()
+            .filter(|&&w| !self.wires.contains_key(w))
+            .map(|w| (w.to_string(), 0))
+            .collect();
+        if undefined.is_empty() {
+            return Ok(());
+        }
+        undefined.sort();
+        undefined.dedup();
+        Err(CircuitError::Undefined(undefined))
+    }
+
+    fn topological_order(&self) -> Result<Vec<&str>, CircuitError> {
+        // how many inputs of each wire are still to be worked out, and the wires each one feeds
+        let mut waiting: HashMap<&str, usize> = HashMap::with_capacity(self.wires.len());
+        let mut feeds: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut undefined = vec![];
+        for (name, (wire, line)) in &self.wires {
+            let mut n = 0;
+            for input in wire.inputs() {
+                if let Operand::Wire(w) = input {
+                    if self.wires.contains_key(w) {
+                        feeds.entry(w).or_default().push(name);
+                        n += 1;
+                    } else {
+                        undefined.push((w.clone(), *line));
+                    }
+                }
+            }
+            waiting.insert(name, n);
+        }
+        if !undefined.is_empty() {
+            undefined.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+            undefined.dedup();
+            return Err(CircuitError::Undefined(undefined));
+        }
+
+        let mut ready: Vec<&str> = waiting
+            .iter()
+            .filter(|&(_, &n)| n == 0)
+            .map(|(&w, _)| w)
+            .collect();
+        let mut order = Vec::with_capacity(self.wires.len());
+        while let Some(w) = ready.pop() {
+            order.push(w);
+            for &next in feeds.get(w).into_iter().flatten() {
+                let n = waiting.get_mut(next).unwrap();
+                *n -= 1;
+                if *n == 0 {
+                    ready.push(next);
+                }
+            }
+        }
+        if order.len() < self.wires.len() {
+            return Err(CircuitError::Cycle(self.find_cycle(&waiting)));
+        }
+        Ok(order)
+    }
+
+    // Every wire still waiting has an input that's waiting too, so following those from any of
+    // them must come back round to a wire already seen.
+    fn find_cycle(&self, waiting: &HashMap<&str, usize>) -> Vec<String> {
+        let is_waiting = |w: &str| waiting[w] > 0;
+        let mut w = *waiting.keys().filter(|w| is_waiting(w)).min().unwrap();
+        let mut path: Vec<&str> = vec![];
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        while !seen.contains_key(w) {
+            seen.insert(w, path.len());
+            path.push(w);
+            w = self.wires[w]
+                .0
+                .inputs()
+                .into_iter()
+                .find_map(|input| match input {
+                    Operand::Wire(i) if is_waiting(i) => Some(i.as_str()),
+                    _ => None,
+                })
+                .unwrap();
+        }
+        // the path went from each wire to an input, so reverse it to follow the signal, and start
+        // from the first name for the same answer every time
+        let mut cycle: Vec<String> = path[seen[w]..]
+            .iter()
+            .rev()
+            .map(|w| w.to_string())
+            .collect();
+        let first = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap();
+        cycle.rotate_left(first);
+        cycle
+    }
 }
 
+// The circuit compiled for evaluating over and over.  Every wire and every distinct signal used as
+// an operand gets a slot, numbered so that each comes after its inputs, and ops[i] works out slot
+// i from those before it.  So one pass through ops gives every signal, and after changing slot i
+// only the ops from i on need running again.
 #[derive(Clone, Debug)]
-enum Wire {
+struct Program {
+    ops: Vec<Op>,
+    ids: HashMap<String, usize>, // wire name to slot
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
     Signal(u16),
-    Direct(String),
-    And(String, String),
-    Or(String, String),
-    Lshift(String, u16),
-    Rshift(String, u16),
-    Not(String),
-}
-
-fn parse_input(input: &str) -> HashMap<String, Wire> {
-    let mut map = HashMap::new();
-    lazy_static! {
-        static ref RE1: Regex = Regex::new(r"^(\d+) -> (\w+)$").unwrap();
-        static ref RE2: Regex = Regex::new(r"^(\w+) (AND|OR) (\w+) -> (\w+)$").unwrap();
-        static ref RE3: Regex = Regex::new(r"^(\w+) (LSHIFT|RSHIFT) (\d+) -> (\w+)$").unwrap();
-        static ref RE4: Regex = Regex::new(r"^NOT (\w+) -> (\w+)$").unwrap();
-        static ref RE5: Regex = Regex::new(r"^(\w+) -> (\w+)$").unwrap();
-    }
-    for line in input.lines() {
-        if let Some(caps) = RE1.captures(line) {
-            map.insert(caps[2].to_owned(), Wire::Signal(caps[1].parse().unwrap()));
-        } else if let Some(caps) = RE2.captures(line) {
-            map.insert(
-                caps[4].to_owned(),
-                match &caps[2] {
-                    "AND" => Wire::And(caps[1].to_owned(), caps[3].to_owned()),
-                    "OR" => Wire::Or(caps[1].to_owned(), caps[3].to_owned()),
-                    _ => unreachable!(),
-                },
-            );
-        } else if let Some(caps) = RE3.captures(line) {
-            map.insert(
-                caps[4].to_owned(),
-                match &caps[2] {
-                    "LSHIFT" => Wire::Lshift(caps[1].to_owned(), caps[3].parse().unwrap()),
-                    "RSHIFT" => Wire::Rshift(caps[1].to_owned(), caps[3].parse().unwrap()),
-                    _ => unreachable!(),
-                },
+    Copy(usize),
+    And(usize, usize),
+    Or(usize, usize),
+    Lshift(usize, u32),
+    Rshift(usize, u32),
+    Not(usize),
+}
+
+impl Program {
+    fn compile(circuit: &Circuit) -> Result<Self, CircuitError> {
+        let order = circuit.topological_order()?;
+        let mut ops = vec![];
+        let mut constants: HashMap<u16, usize> = HashMap::new();
+        for (wire, _) in circuit.wires.values() {
+            if let Wire::Direct(Operand::Signal(_)) = wire {
+                continue; // that's an op of its own
+            }
+            for input in wire.inputs() {
+                if let &Operand::Signal(v) = input {
+                    constants.entry(v).or_insert_with(|| {
+                        ops.push(Op::Signal(v));
+                        ops.len() - 1
+                    });
+                }
+            }
+        }
+        let ids: HashMap<String, usize> = order
+            .iter()
+            .enumerate()
+            .map(|(i, &name)| (name.to_string(), ops.len() + i))
+            .collect();
+        let slot = |input: &Operand| match input {
+            Operand::Wire(w) => ids[w],
+            Operand::Signal(v) => constants[v],
+        };
+        for name in order {
+            ops.push(match &circuit.wires[name].0 {
+                Wire::Direct(Operand::Signal(v)) => Op::Signal(*v),
+                Wire::Direct(a) => Op::Copy(slot(a)),
+                Wire::And(a, b) => Op::And(slot(a), slot(b)),
+                Wire::Or(a, b) => Op::Or(slot(a), slot(b)),
+                Wire::Lshift(a, v) => Op::Lshift(slot(a), *v as u32),
+                Wire::Rshift(a, v) => Op::Rshift(slot(a), *v as u32),
+                Wire::Not(a) => Op::Not(slot(a)),
+            });
+        }
+        Ok(Self { ops, ids })
+    }
+
+    fn id(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    // replaces whatever drives the wire in slot id
+    fn set_signal(&mut self, id: usize, signal: u16) {
+        self.ops[id] = Op::Signal(signal);
+    }
+
+    // every slot's signal
+    fn evaluate(&self) -> Vec<u16> {
+        let mut signals = vec![0; self.ops.len()];
+        self.run_from(0, &mut signals);
+        signals
+    }
+
+    // works out slots from start on, given the signals before it.  Shifting by 16 or more leaves
+    // nothing.
+    fn run_from(&self, start: usize, signals: &mut [u16]) {
+        for i in start..self.ops.len() {
+            signals[i] = match self.ops[i] {
+                Op::Signal(v) => v,
+                Op::Copy(a) => signals[a],
+                Op::And(a, b) => signals[a] & signals[b],
+                Op::Or(a, b) => signals[a] | signals[b],
+                Op::Lshift(a, v) => signals[a].checked_shl(v).unwrap_or(0),
+                Op::Rshift(a, v) => signals[a].checked_shr(v).unwrap_or(0),
+                Op::Not(a) => !signals[a],
+            };
+        }
+    }
+
+    // the signal on output for each signal from 0 to 65535 on input, which is driven by that
+    // signal instead of whatever drives it now
+    #[allow(dead_code)]
+    fn sweep(&self, input: usize, output: usize) -> Vec<u16> {
+        let mut program = self.clone();
+        let mut signals = self.evaluate();
+        (0..=u16::MAX)
+            .map(|v| {
+                program.set_signal(input, v);
+                program.run_from(input, &mut signals);
+                signals[output]
+            })
+            .collect()
+    }
+}
+
+// Simplifying circuits
+#[allow(dead_code)]
+impl Circuit {
+    // An equivalent circuit for the signals on outputs, given any signals driving the wires in
+    // inputs.  Signals are worked out wherever they don't depend on inputs, identities like
+    // `x AND 65535`, `NOT NOT x` and shifts by 0 are simplified away, wires that just copy others
+    // are bypassed, and anything the outputs don't use is dropped.  Each wire keeps its line.
+    // Outputs and inputs must be wires in the circuit.
+    fn optimize(&self, outputs: &[&str], inputs: &[&str]) -> Result<Circuit, CircuitError> {
+        self.check_defined(&[outputs, inputs].concat())?;
+        // what each wire has become so far.  Anything that reduces to a signal or another wire
+        // is replaced by that wherever it's used.
+        let mut simplified: HashMap<&str, Wire> = HashMap::new();
+        for name in self.topological_order()? {
+            let wire = &self.wires[name].0;
+            let wire = if inputs.contains(&name) {
+                wire.clone()
+            } else {
+                simplify(
+                    wire.map_inputs(|op| match op {
+                        Operand::Wire(w) if !inputs.contains(&w.as_str()) => {
+                            match &simplified[w.as_str()] {
+                                Wire::Direct(a) => a.clone(),
+                                _ => op.clone(),
+                            }
+                        }
+                        _ => op.clone(),
+                    }),
+                    &simplified,
+                    inputs,
+                )
+            };
+            simplified.insert(name, wire);
+        }
+
+        let mut wires = HashMap::new();
+        let mut stack: Vec<&str> = outputs.to_vec();
+        while let Some(name) = stack.pop() {
+            if wires.contains_key(name) {
+                continue;
+            }
+            let wire = &simplified[name];
+            for input in wire.inputs() {
+                if let Operand::Wire(w) = input {
+                    stack.push(w);
+                }
+            }
+            wires.insert(name.to_string(), (wire.clone(), self.wires[name].1));
+        }
+        Ok(Circuit { wires })
+    }
+
+    // in the puzzle's syntax
+    fn to_puzzle(&self) -> String {
+        let mut out = String::new();
+        for name in self.listing_order() {
+            writeln!(out, "{} -> {}", self.wires[name].0, name).unwrap();
+        }
+        out
+    }
+
+    // the wires in the order of their lines, except that each comes after its inputs
+    fn listing_order(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.wires.keys().map(|w| w.as_str()).collect();
+        names.sort_by_key(|&w| (self.wires[w].1, w));
+        let mut started = HashSet::new();
+        let mut order = vec![];
+        for name in names {
+            // (wire, whether its inputs are done)
+            let mut stack = vec![(name, false)];
+            while let Some((w, inputs_done)) = stack.pop() {
+                let Some((wire, _)) = self.wires.get(w) else {
+                    continue; // undefined
+                };
+                if inputs_done {
+                    order.push(w);
+                } else if started.insert(w) {
+                    stack.push((w, true));
+                    for input in wire.inputs().into_iter().rev() {
+                        if let Operand::Wire(i) = input {
+                            stack.push((i, false));
+                        }
+                    }
+                }
+            }
+        }
+        order
+    }
+}
+
+// uses what the wires a wire's inputs come from have been simplified to, except for those in
+// inputs, which could be driven by anything
+fn simplify(wire: Wire, simplified: &HashMap<&str, Wire>, inputs: &[&str]) -> Wire {
+    use Operand::Signal;
+    let signals: Option<Vec<u16>> = wire
+        .inputs()
+        .iter()
+        .map(|input| match input {
+            Signal(v) => Some(*v),
+            Operand::Wire(_) => None,
+        })
+        .collect();
+    if let Some(signals) = signals {
+        let mut signals = signals.into_iter();
+        return Wire::Direct(Signal(wire.signal(|_| signals.next().unwrap())));
+    }
+    match wire {
+        Wire::And(Signal(0), _) | Wire::And(_, Signal(0)) => Wire::Direct(Signal(0)),
+        Wire::And(Signal(u16::MAX), a) | Wire::And(a, Signal(u16::MAX)) => Wire::Direct(a),
+        Wire::Or(Signal(0), a) | Wire::Or(a, Signal(0)) => Wire::Direct(a),
+        Wire::Or(Signal(u16::MAX), _) | Wire::Or(_, Signal(u16::MAX)) => {
+            Wire::Direct(Signal(u16::MAX))
+        }
+        Wire::And(a, b) | Wire::Or(a, b) if a == b => Wire::Direct(a),
+        Wire::Lshift(a, 0) | Wire::Rshift(a, 0) => Wire::Direct(a),
+        Wire::Lshift(_, 16..) | Wire::Rshift(_, 16..) => Wire::Direct(Signal(0)),
+        Wire::Not(Operand::Wire(w)) if !inputs.contains(&w.as_str()) => {
+            match &simplified[w.as_str()] {
+                Wire::Not(a) => Wire::Direct(a.clone()),
+                _ => Wire::Not(Operand::Wire(w)),
+            }
+        }
+        wire => wire,
+    }
+}
+
+// Exporting circuits for other tools.  Given the signals (from evaluate), each wire is labelled
+// with its own.
+#[allow(dead_code)]
+impl Circuit {
+    // Graphviz: a node for each gate and an edge for each wire from the gate driving it to each
+    // gate it feeds.  Wires that feed nothing lead to a dot.  Signals used as operands are nodes
+    // of their own.
+    fn to_dot(&self, signals: Option<&HashMap<String, u16>>) -> String {
+        let label = |w: &str| match signals.and_then(|s| s.get(w)) {
+            Some(v) => format!("{} = {}", w, v),
+            None => w.to_string(),
+        };
+        let mut out = String::from("digraph circuit {\n    rankdir=LR;\n    node [shape=box];\n");
+        let mut constants = HashSet::new();
+        let mut used = HashSet::new();
+        for (wire, _) in self.wires.values() {
+            if let Wire::Direct(Operand::Signal(_)) = wire {
+                continue; // the gate shows the signal
+            }
+            for input in wire.inputs() {
+                match input {
+                    Operand::Signal(v) => {
+                        constants.insert(*v);
+                    }
+                    Operand::Wire(w) => {
+                        used.insert(w.as_str());
+                    }
+                }
+            }
+        }
+        let mut constants: Vec<_> = constants.into_iter().collect();
+        constants.sort_unstable();
+        for v in constants {
+            writeln!(out, "    c{} [label=\"{}\", shape=plaintext];", v, v).unwrap();
+        }
+        for name in self.listing_order() {
+            let wire = &self.wires[name].0;
+            let gate = match wire {
+                Wire::Direct(Operand::Signal(v)) => v.to_string(),
+                Wire::Direct(_) => "=".to_string(),
+                Wire::And(..) => "AND".to_string(),
+                Wire::Or(..) => "OR".to_string(),
+                Wire::Lshift(_, v) => format!("LSHIFT {}", v),
+                Wire::Rshift(_, v) => format!("RSHIFT {}", v),
+                Wire::Not(_) => "NOT".to_string(),
+            };
+            writeln!(out, "    g_{} [label=\"{}\"];", name, gate).unwrap();
+            if !matches!(wire, Wire::Direct(Operand::Signal(_))) {
+                for input in wire.inputs() {
+                    match input {
+                        Operand::Signal(v) => writeln!(out, "    c{} -> g_{};", v, name),
+                        Operand::Wire(w) => {
+                            writeln!(out, "    g_{} -> g_{} [label=\"{}\"];", w, name, label(w))
+                        }
+                    }
+                    .unwrap();
+                }
+            }
+            if !used.contains(name) {
+                writeln!(out, "    out_{} [shape=point];", name).unwrap();
+                writeln!(
+                    out,
+                    "    g_{} -> out_{} [label=\"{}\"];",
+                    name,
+                    name,
+                    label(name)
+                )
+                .unwrap();
+            }
+        }
+        out += "}\n";
+        out
+    }
+
+    // A Verilog module with a 16 bit output for each wire that feeds nothing
+    fn to_verilog(&self, module: &str, signals: Option<&HashMap<String, u16>>) -> String {
+        let names = self.listing_order();
+        let used: HashSet<&str> = self
+            .wires
+            .values()
+            .flat_map(|(wire, _)| wire.inputs())
+            .filter_map(|input| match input {
+                Operand::Wire(w) => Some(w.as_str()),
+                Operand::Signal(_) => None,
+            })
+            .collect();
+        let (outputs, internal): (Vec<&str>, Vec<&str>) =
+            names.iter().partition(|&&w| !used.contains(w));
+        let operand = |a: &Operand| match a {
+            Operand::Wire(w) => verilog_ident(w),
+            Operand::Signal(v) => format!("16'd{}", v),
+        };
+
+        let mut out = String::new();
+        let ports: Vec<_> = outputs
+            .iter()
+            .map(|w| format!("    output wire [15:0] {}", verilog_ident(w)))
+            .collect();
+        writeln!(out, "module {}(\n{}\n);", module, ports.join(",\n")).unwrap();
+        for w in &internal {
+            writeln!(out, "    wire [15:0] {};", verilog_ident(w)).unwrap();
+        }
+        for &name in &names {
+            let expression = match &self.wires[name].0 {
+                Wire::Direct(a) => operand(a),
+                Wire::And(a, b) => format!("{} & {}", operand(a), operand(b)),
+                Wire::Or(a, b) => format!("{} | {}", operand(a), operand(b)),
+                Wire::Lshift(a, v) => format!("{} << {}", operand(a), v),
+                Wire::Rshift(a, v) => format!("{} >> {}", operand(a), v),
+                Wire::Not(a) => format!("~{}", operand(a)),
+            };
+            write!(out, "    assign {} = {};", verilog_ident(name), expression).unwrap();
+            if let Some(v) = signals.and_then(|s| s.get(name)) {
+                write!(out, " // {}", v).unwrap();
+            }
+            out += "\n";
+        }
+        out += "endmodule\n";
+        out
+    }
+}
+
+// wire names that are Verilog keywords are escaped, which takes a space after them
+fn verilog_ident(name: &str) -> String {
+    const KEYWORDS: [&str; 28] = [
+        "always", "and", "assign", "begin", "buf", "case", "default", "do", "else", "end", "for",
+        "if", "initial", "inout", "input", "integer", "module", "nand", "nor", "not", "or",
+        "output", "reg", "tri", "while", "wire", "xnor", "xor",
+    ];
+    if KEYWORDS.contains(&name) {
+        format!("\\{} ", name)
+    } else {
+        name.to_string()
+    }
+}
+
+// Sets of signals for the free wires, from solve_for
+#[allow(dead_code)]
+#[derive(Debug)]
+struct Solutions {
+    found: Vec<HashMap<String, u16>>,
+    // false if the limit stopped the search with more still to find
+    complete: bool,
+}
+
+// Running the circuit backwards
+#[allow(dead_code)]
+impl Circuit {
+    // Up to limit different sets of signals for the wires in free that put signal on target, with
+    // every other wire driven as in the circuit.  It's an error if there are none at all, or if
+    // the target doesn't depend on one of the free wires, which could then be anything.  Each
+    // wire the target depends on becomes 16 boolean variables, one per bit, and each gate
+    // clauses relating them, for a SAT solver.
+    fn solve_for(
+        &self,
+        target: &str,
+        signal: u16,
+        free: &[&str],
+        limit: usize,
+    ) -> Result<Solutions, CircuitError> {
+        self.check_defined(&[&[target], free].concat())?;
+        let order = self.topological_order()?;
+        let mut solver = Solver::new();
+        let one = solver.new_var();
+        solver.add_clause(&[one]);
+        let constant = |v: u16| -> [Lit; 16] {
+            std::array::from_fn(|i| if v >> i & 1 == 1 { one } else { !one })
+        };
+        let free_bits: Vec<[Lit; 16]> = free
+            .iter()
+            .map(|_| std::array::from_fn(|_| solver.new_var()))
+            .collect();
+
+        // only the wires the target depends on
+        let mut needed = HashSet::new();
+        let mut stack = vec![target];
+        while let Some(w) = stack.pop() {
+            if needed.insert(w) && !free.contains(&w) {
+                for input in self.wires[w].0.inputs() {
+                    if let Operand::Wire(i) = input {
+                        stack.push(i);
+                    }
+                }
+            }
+        }
+        let unused: Vec<String> = free
+            .iter()
+            .filter(|w| !needed.contains(*w))
+            .map(|w| w.to_string())
+            .collect();
+        if !unused.is_empty() {
+            return Err(CircuitError::Unused(target.to_string(), unused));
+        }
+        let mut bits: HashMap<&str, [Lit; 16]> = HashMap::new();
+        for (w, b) in free.iter().zip(&free_bits) {
+            bits.insert(w, *b);
+        }
+        for name in order {
+            if !needed.contains(name) || bits.contains_key(name) {
+                continue;
+            }
+            let operand = |a: &Operand| match a {
+                Operand::Wire(w) => bits[w.as_str()],
+                Operand::Signal(v) => constant(*v),
+            };
+            let wire_bits = match &self.wires[name].0 {
+                Wire::Direct(a) => operand(a),
+                Wire::And(a, b) => {
+                    let (a, b) = (operand(a), operand(b));
+                    std::array::from_fn(|i| {
+                        let z = solver.new_var();
+                        solver.add_clause(&[!z, a[i]]);
+                        solver.add_clause(&[!z, b[i]]);
+                        solver.add_clause(&[z, !a[i], !b[i]]);
+                        z
+                    })
+                }
+                Wire::Or(a, b) => {
+                    let (a, b) = (operand(a), operand(b));
+                    std::array::from_fn(|i| {
+                        let z = solver.new_var();
+                        solver.add_clause(&[z, !a[i]]);
+                        solver.add_clause(&[z, !b[i]]);
+                        solver.add_clause(&[!z, a[i], b[i]]);
+                        z
+                    })
+                }
+                Wire::Lshift(a, v) => {
+                    let a = operand(a);
+                    std::array::from_fn(|i| match i.checked_sub(*v as usize) {
+                        Some(j) => a[j],
+                        None => !one,
+                    })
+                }
+                Wire::Rshift(a, v) => {
+                    let a = operand(a);
+                    std::array::from_fn(|i| a.get(i + *v as usize).copied().unwrap_or(!one))
+                }
+                Wire::Not(a) => operand(a).map(|l| !l),
+            };
+            bits.insert(name, wire_bits);
+        }
+        for (&lit, &want) in bits[target].iter().zip(&constant(signal)) {
+            solver.add_clause(&[if want == one { lit } else { !lit }]);
+        }
+
+        let mut found = vec![];
+        let complete = loop {
+            let Some(model) = solver.solve() else {
+                break true;
+            };
+            if found.len() == limit {
+                break false;
+            }
+            let value = |b: &[Lit; 16]| {
+                (0..16).fold(0u16, |v, i| {
+                    v | ((model[b[i].var()] == b[i].is_positive()) as u16) << i
+                })
+            };
+            found.push(
+                free.iter()
+                    .zip(&free_bits)
+                    .map(|(w, b)| (w.to_string(), value(b)))
+                    .collect(),
             );
-        } else if let Some(caps) = RE4.captures(line) {
-            map.insert(caps[2].to_owned(), Wire::Not(caps[1].to_owned()));
-        } else if let Some(caps) = RE5.captures(line) {
-            map.insert(caps[2].to_owned(), Wire::Direct(caps[1].to_owned()));
-        } else {
-            panic!("unexpected instruction: {}", line);
+            // rule this one out
+            let blocking: Vec<Lit> = free_bits
+                .iter()
+                .flatten()
+                .map(|&l| if model[l.var()] { !l } else { l })
+                .collect();
+            if !solver.add_clause(&blocking) {
+                break true;
+            }
+        };
+        if found.is_empty() && complete {
+            return Err(CircuitError::Unreachable(target.to_string(), signal));
         }
+        Ok(Solutions { found, complete })
     }
+}
 
-    map
+fn parse_input(input: &str) -> Result<Circuit, ParseError> {
+    let mut wires: HashMap<String, (Wire, usize)> = HashMap::new();
+    let mut line = 0;
+    parse::lines(input, |p| {
+        line += 1;
+        let (name, wire) = parse_line(p)?;
+        if let Some((_, first)) = wires.get(&name) {
+            // at the name, which ends the line
+            return Err(p.error_at(
+                p.position() - name.len(),
+                format!("wire {} is already defined on line {}", name, first),
+            ));
+        }
+        wires.insert(name, (wire, line));
+        Ok(())
+    })?;
+    Ok(Circuit { wires })
+}
+
+// examples:
+// 123 -> x
+// x AND y -> d
+// 1 AND y -> e
+// x LSHIFT 2 -> f
+// NOT x -> h
+// x -> y
+fn parse_line(p: &mut Parser) -> Result<(String, Wire), ParseError> {
+    let wire = if p.try_literal("NOT ") {
+        Wire::Not(parse_operand(p)?)
+    } else {
+        let a = parse_operand(p)?;
+        if p.rest().starts_with(" -> ") {
+            Wire::Direct(a)
+        } else {
+            p.literal(" ")?;
+            let gate = p.keyword(&[
+                ("AND", "AND"),
+                ("OR", "OR"),
+                ("LSHIFT", "LSHIFT"),
+                ("RSHIFT", "RSHIFT"),
+            ])?;
+            p.literal(" ")?;
+            match gate {
+                "AND" => Wire::And(a, parse_operand(p)?),
+                "OR" => Wire::Or(a, parse_operand(p)?),
+                "LSHIFT" => Wire::Lshift(a, p.integer()?),
+                _ => Wire::Rshift(a, p.integer()?),
+            }
+        }
+    };
+    p.literal(" -> ")?;
+    Ok((p.ident()?.to_owned(), wire))
+}
+
+fn parse_operand(p: &mut Parser) -> Result<Operand, ParseError> {
+    if p.rest().starts_with(|c: char| c.is_ascii_digit()) {
+        Ok(Operand::Signal(p.integer()?))
+    } else {
+        Ok(Operand::Wire(p.ident()?.to_owned()))
+    }
 }
 
 #[test]
@@ -109,14 +846,385 @@ y RSHIFT 2 -> g
 NOT x -> h
 NOT y -> i
 ";
-    let wires = parse_input(test_input);
-    dbg!(&wires);
-    assert_eq!(72, measure(&wires, "d", &mut HashMap::new()));
-    assert_eq!(507, measure(&wires, "e", &mut HashMap::new()));
-    assert_eq!(492, measure(&wires, "f", &mut HashMap::new()));
-    assert_eq!(114, measure(&wires, "g", &mut HashMap::new()));
-    assert_eq!(65412, measure(&wires, "h", &mut HashMap::new()));
-    assert_eq!(65079, measure(&wires, "i", &mut HashMap::new()));
-    assert_eq!(123, measure(&wires, "x", &mut HashMap::new()));
-    assert_eq!(456, measure(&wires, "y", &mut HashMap::new()));
+    let signals = parse_input(test_input).unwrap().evaluate().unwrap();
+    assert_eq!(72, signals["d"]);
+    assert_eq!(507, signals["e"]);
+    assert_eq!(492, signals["f"]);
+    assert_eq!(114, signals["g"]);
+    assert_eq!(65412, signals["h"]);
+    assert_eq!(65079, signals["i"]);
+    assert_eq!(123, signals["x"]);
+    assert_eq!(456, signals["y"]);
+
+    // defined in any order, with signals as operands
+    let signals = parse_input("c LSHIFT 20 -> a\n1 AND b -> c\n7 -> b\n")
+        .unwrap()
+        .evaluate()
+        .unwrap();
+    assert_eq!((0, 1), (signals["a"], signals["c"]));
+}
+
+#[test]
+fn test_errors() {
+    let error = |s| {
+        parse_input(s)
+            .unwrap()
+            .evaluate()
+            .err()
+            .map(|e| e.to_string())
+    };
+    assert_eq!(
+        Some("undefined wires: q (line 2), r (line 2), q (line 3)".to_string()),
+        error("1 -> x\nq OR r -> y\nNOT q -> z\n")
+    );
+    assert_eq!(
+        Some("wires in a loop: b -> c -> d -> b".to_string()),
+        error("5 -> a\na AND d -> b\nNOT b -> c\nc -> d\nd -> e\n")
+    );
+    assert_eq!(
+        Some("wires in a loop: x -> x".to_string()),
+        error("x OR x -> x\n")
+    );
+    assert_eq!(
+        Some("line 3, column 6: wire x is already defined on line 1".to_string()),
+        parse_input("1 -> x\n2 -> y\n3 -> x\n")
+            .err()
+            .map(|e| e.to_string())
+    );
+
+    // too deep to have worked out recursively
+    let mut chain = String::from("1 -> w0\n");
+    for i in 1..200_000 {
+        chain += &format!("NOT w{} -> w{}\n", i - 1, i);
+    }
+    let signals = parse_input(&chain).unwrap().evaluate().unwrap();
+    assert_eq!(1, signals["w199998"]);
+    assert_eq!(!1, signals["w199999"]);
+}
+
+// A random circuit in the puzzle's syntax, with wires w0, w1, ... each using ones before it (or
+// signals), so w0 and w1 are inputs and the last wire depends on lots of others
+#[cfg(test)]
+fn random_circuit(n: usize, seed: u64) -> String {
+    use crate::rng::Rng;
+
+    let mut rng = Rng::new(seed);
+    let mut next = |m: usize| rng.below(m);
+    let mut circuit = String::new();
+    for i in 0..n {
+        let operand = |next: &mut dyn FnMut(usize) -> usize| {
+            if i == 0 || next(8) == 0 {
+                next(65536).to_string()
+            } else {
+                // mostly recent wires, so the circuit is deep
+                format!("w{}", i - 1 - next(i.min(10)))
+            }
+        };
+        let (a, b) = (operand(&mut next), operand(&mut next));
+        let gate = match next(6) {
+            0 => a,
+            1 => format!("{} AND {}", a, b),
+            2 => format!("{} OR {}", a, b),
+            3 => format!("{} LSHIFT {}", a, next(17)),
+            4 => format!("{} RSHIFT {}", a, next(17)),
+            _ => format!("NOT {}", a),
+        };
+        circuit += &format!("{} -> w{}\n", gate, i);
+    }
+    circuit
+}
+
+#[test]
+fn test_program() {
+    let circuit = parse_input("NOT b -> c\n7 -> b\nc AND 12 -> a\n3 OR 12 -> d\n").unwrap();
+    let mut program = Program::compile(&circuit).unwrap();
+    // 3 and 12 come first, then the wires
+    assert_eq!(2 + 4, program.ops.len());
+    assert!(program.ids.values().all(|&id| id >= 2));
+    let (a, b) = (program.id("a").unwrap(), program.id("b").unwrap());
+    assert_eq!(8, program.evaluate()[a]);
+    program.set_signal(b, 12);
+    assert_eq!(0, program.evaluate()[a]);
+    let sweep = program.sweep(b, a);
+    assert_eq!(65536, sweep.len());
+    for v in [0, 4, 7, 12, 1000, 65535] {
+        assert_eq!(!v & 12, sweep[v as usize]);
+    }
+
+    // sweeping agrees with changing the circuit and evaluating it again
+    let circuit = parse_input(&random_circuit(300, 1)).unwrap();
+    let program = Program::compile(&circuit).unwrap();
+    let (input, output) = (program.id("w1").unwrap(), program.id("w299").unwrap());
+    let sweep = program.sweep(input, output);
+    let mut changed = program.clone();
+    for v in [0, 1, 2, 255, 256, 40000, 65535] {
+        changed.set_signal(input, v);
+        assert_eq!(changed.evaluate()[output], sweep[v as usize]);
+    }
+}
+
+#[test]
+fn test_optimize() {
+    let circuit = parse_input(
+        "\
+b AND 65535 -> c
+NOT c -> d
+NOT d -> e
+e LSHIFT 0 -> f
+f OR g -> a
+3 AND 4 -> g
+5 -> b
+x OR b -> unused
+123 -> x
+",
+    )
+    .unwrap();
+    assert_eq!(
+        "5 -> b\nb -> a\n",
+        circuit.optimize(&["a"], &["b"]).unwrap().to_puzzle()
+    );
+    assert_eq!(
+        "5 -> a\n",
+        circuit.optimize(&["a"], &[]).unwrap().to_puzzle()
+    );
+    // b could be driven by anything, so can't be seen through
+    let inverted = parse_input("NOT x -> b\nNOT b -> a\n7 -> x\n").unwrap();
+    assert_eq!(
+        "7 -> x\nNOT x -> b\nNOT b -> a\n",
+        inverted.optimize(&["a"], &["b"]).unwrap().to_puzzle()
+    );
+    assert_eq!(
+        "5 -> b\nb -> c\nNOT b -> d\n123 OR b -> unused\n",
+        circuit
+            .optimize(&["unused", "d", "c"], &["b"])
+            .unwrap()
+            .to_puzzle()
+    );
+    assert_eq!(
+        Some(CircuitError::Undefined(vec![("z".to_string(), 0)])),
+        circuit.optimize(&["a", "z"], &["b"]).err()
+    );
+    // which parses back to the same thing
+    let printed = circuit.to_puzzle();
+    assert_eq!(printed, parse_input(&printed).unwrap().to_puzzle());
+    assert!(printed.starts_with("5 -> b\nb AND 65535 -> c\n"));
+
+    // the same signals for every input, from fewer wires
+    for seed in 1..5 {
+        let circuit = parse_input(&random_circuit(300, seed)).unwrap();
+        let optimized = circuit.optimize(&["w299", "w250"], &["w1"]).unwrap();
+        assert!(optimized.wires.len() < circuit.wires.len());
+        let (original, optimized) = (
+            Program::compile(&circuit).unwrap(),
+            Program::compile(&optimized).unwrap(),
+        );
+        for output in ["w299", "w250"] {
+            // w1 is dropped if the output doesn't depend on it
+            let sweep = |p: &Program| match p.id("w1") {
+                Some(input) => p.sweep(input, p.id(output).unwrap()),
+                None => vec![p.evaluate()[p.id(output).unwrap()]; 65536],
+            };
+            assert_eq!(sweep(&original), sweep(&optimized));
+        }
+    }
+}
+
+#[test]
+fn test_export() {
+    let circuit =
+        parse_input("123 -> x\n456 -> or\nx AND or -> d\n1 OR x -> a\nNOT x -> b\n").unwrap();
+    let signals = circuit.evaluate().unwrap();
+    assert_eq!(
+        "\
+digraph circuit {
+    rankdir=LR;
+    node [shape=box];
+    c1 [label=\"1\", shape=plaintext];
+    g_x [label=\"123\"];
+    g_or [label=\"456\"];
+    g_d [label=\"AND\"];
+    g_x -> g_d [label=\"x = 123\"];
+    g_or -> g_d [label=\"or = 456\"];
+    out_d [shape=point];
+    g_d -> out_d [label=\"d = 72\"];
+    g_a [label=\"OR\"];
+    c1 -> g_a;
+    g_x -> g_a [label=\"x = 123\"];
+    out_a [shape=point];
+    g_a -> out_a [label=\"a = 123\"];
+    g_b [label=\"NOT\"];
+    g_x -> g_b [label=\"x = 123\"];
+    out_b [shape=point];
+    g_b -> out_b [label=\"b = 65412\"];
+}
+",
+        circuit.to_dot(Some(&signals))
+    );
+    assert_eq!(
+        "\
+module circuit(
+    output wire [15:0] d,
+    output wire [15:0] a,
+    output wire [15:0] b
+);
+    wire [15:0] x;
+    wire [15:0] \\or ;
+    assign x = 16'd123; // 123
+    assign \\or  = 16'd456; // 456
+    assign d = x & \\or ; // 72
+    assign a = 16'd1 | x; // 123
+    assign b = ~x; // 65412
+endmodule
+",
+        circuit.to_verilog("circuit", Some(&signals))
+    );
+    assert!(!circuit.to_verilog("circuit", None).contains("//"));
+    assert!(circuit.to_dot(None).contains("g_a -> out_a [label=\"a\"];"));
+}
+
+#[test]
+fn test_solve() {
+    let circuit = parse_input("123 -> x\n456 -> y\nx AND y -> d\nd OR 1 -> e\n").unwrap();
+    // with both inputs free, x and y need every bit of 72 and nothing else in common
+    let solutions = circuit.solve_for("d", 72, &["x", "y"], 20).unwrap();
+    assert_eq!(20, solutions.found.len());
+    assert!(!solutions.complete);
+    for s in &solutions.found {
+        assert_eq!(72, s["x"] & s["y"]);
+    }
+    // y is 456, with bits 3, 6, 7 and 8, so x needs bits 3 and 6, can't have 7 or 8, and can
+    // have any of the other 12.  A high enough limit finds them all.
+    let x_only = circuit.solve_for("d", 72, &["x"], 1 << 20).unwrap();
+    assert_eq!(1 << 12, x_only.found.len());
+    assert!(x_only.complete);
+    assert!(x_only.found.iter().all(|s| s["x"] & 456 == 72));
+    // exactly as many as the limit is still everything
+    assert!(
+        circuit
+            .solve_for("d", 72, &["x"], 1 << 12)
+            .unwrap()
+            .complete
+    );
+    assert!(
+        !circuit
+            .solve_for("d", 72, &["x"], (1 << 12) - 1)
+            .unwrap()
+            .complete
+    );
+    let mut d: Vec<u16> = circuit
+        .solve_for("e", 73, &["d"], 5)
+        .unwrap()
+        .found
+        .iter()
+        .map(|s| s["d"])
+        .collect();
+    d.sort_unstable();
+    assert_eq!(vec![72, 73], d);
+    let errors = |target, free| {
+        circuit
+            .solve_for(target, 0, free, 1)
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!("undefined wires: q", errors("q", &["x"]));
+    assert_eq!("undefined wires: p, q", errors("d", &["x", "q", "p"]));
+    let error = |target, signal, free| {
+        circuit
+            .solve_for(target, signal, free, 1)
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        "no signals on the free wires put 1 on d",
+        error("d", 1, &["x"])
+    );
+    // e is always odd
+    assert_eq!(
+        "no signals on the free wires put 72 on e",
+        error("e", 72, &["x", "y"])
+    );
+    // x only reaches e through d
+    assert_eq!(
+        "e doesn't depend on free wires: x",
+        error("e", 73, &["x", "d"])
+    );
+    assert_eq!(
+        "d doesn't depend on free wires: e",
+        error("d", 72, &["x", "e", "y"])
+    );
+
+    // against trying every signal
+    let circuit = parse_input(&adder_circuit(12345)).unwrap();
+    let program = Program::compile(&circuit).unwrap();
+    let b = program.id("b").unwrap();
+    for (output, limit) in [("sum", 5), ("high", 300)] {
+        let sweep = program.sweep(b, program.id(output).unwrap());
+        for v in [0, 1, 12345, 54321, 65535] {
+            let target = sweep[v];
+            let expected: Vec<u16> = (0..=u16::MAX)
+                .filter(|&v| sweep[v as usize] == target)
+                .collect();
+            let mut found: Vec<u16> = circuit
+                .solve_for(output, target, &["b"], limit)
+                .unwrap()
+                .found
+                .iter()
+                .map(|s| s["b"])
+                .collect();
+            found.sort_unstable();
+            assert_eq!(expected, found);
+        }
+    }
+    assert_eq!(
+        vec![65535 - 12345 + 1 + 7],
+        circuit
+            .solve_for("sum", 7, &["b"], 5)
+            .unwrap()
+            .found
+            .iter()
+            .map(|s| s["b"])
+            .collect::<Vec<_>>()
+    );
+}
+
+// b + k on sum, from gates: adding without carrying is XOR, which is (x OR y) AND NOT (x AND
+// y), and the carries are the ANDs shifted left, which are added in the same way until there
+// can't be any left.  high is sum with the low 8 bits cleared.
+#[cfg(test)]
+fn adder_circuit(k: u16) -> String {
+    let mut circuit = format!("{} -> k\n0 -> b\n", k);
+    let (mut x, mut y) = ("b".to_string(), "k".to_string());
+    for i in 0..16 {
+        circuit += &format!("{} OR {} -> o{}\n", x, y, i);
+        circuit += &format!("{} AND {} -> n{}\n", x, y, i);
+        circuit += &format!("NOT n{} -> m{}\n", i, i);
+        circuit += &format!("o{} AND m{} -> s{}\n", i, i, i);
+        circuit += &format!("n{} LSHIFT 1 -> c{}\n", i, i);
+        (x, y) = (format!("s{}", i), format!("c{}", i));
+    }
+    circuit += &format!("{} -> sum\nsum AND 65280 -> high\n", x);
+    circuit
+}
+
+// cargo test --release bench_program -- --ignored --nocapture
+#[test]
+#[ignore]
+fn bench_program() {
+    use std::time::Instant;
+
+    let circuit = parse_input(&random_circuit(340, 42)).unwrap();
+    let program = Program::compile(&circuit).unwrap();
+    let (input, output) = (program.id("w0").unwrap(), program.id("w339").unwrap());
+    let t0 = Instant::now();
+    for _ in 0..30 {
+        program.sweep(input, output);
+    }
+    let seconds = t0.elapsed().as_secs_f64();
+    println!(
+        "{} evaluations of {} ops in {:.3}s ({:.0}/s)",
+        30 * 65536,
+        program.ops.len(),
+        seconds,
+        30.0 * 65536.0 / seconds
+    );
 }
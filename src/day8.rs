@@ -104,6 +104,6 @@ fn test() {
     assert_eq!(6, test4.len());
     assert_eq!(1, decode(test4).len());
     assert_eq!(11, encode(test4).len());
-    assert_eq!(12, part1(&vec![test1, test2, test3, test4]));
-    assert_eq!(19, part2(&vec![test1, test2, test3, test4]));
+    assert_eq!(12, part1(&[test1, test2, test3, test4]));
+    assert_eq!(19, part2(&[test1, test2, test3, test4]));
 }
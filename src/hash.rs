@@ -0,0 +1,478 @@
+// Hash functions for the nonce-mining puzzles: MD5, SHA-1 and SHA-256.  Md5::digest comes from the
+// md5 crate, while Md5::numbered (and Md5Prefix) use the MD5 implemented below; SHA-1 and SHA-256
+// are implemented here too.
+#![allow(dead_code)]
+
+pub trait HashFunction: Send + 'static {
+    type Digest: AsRef<[u8]> + Copy + Send + 'static;
+    const NAME: &'static str;
+
+    fn digest(data: &[u8]) -> Self::Digest;
+
+    // Digests of the key followed by each of start, start + step, start + 2 * step, ... written
+    // in decimal, in that order.  Backends can speed this up by not re-hashing the key each time.
+    fn numbered(key: &[u8], start: u64, step: u64) -> impl Iterator<Item = (u64, Self::Digest)> {
+        (0..).map(move |i| {
+            let num = start + i * step;
+            let mut data = key.to_vec();
+            data.extend_from_slice(num.to_string().as_bytes());
+            (num, Self::digest(&data))
+        })
+    }
+}
+
+pub struct Md5;
+
+impl HashFunction for Md5 {
+    type Digest = [u8; 16];
+    const NAME: &'static str = "md5";
+
+    fn digest(data: &[u8]) -> [u8; 16] {
+        md5::compute(data).0
+    }
+
+    fn numbered(key: &[u8], start: u64, step: u64) -> impl Iterator<Item = (u64, [u8; 16])> {
+        Md5Numbered::<MD5_LANES>::new(key, start, step)
+    }
+}
+
+// MD5 implemented here so the numbered digests can skip the work they have in common: the state
+// after the whole 64-byte blocks of the key is worked out once, the number is kept as decimal
+// digits and added to in place, and several numbers are hashed side by side in lanes (which the
+// compiler can turn into SIMD instructions).
+
+const MD5_LANES: usize = 8;
+
+const MD5_INIT: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+const MD5_SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+const MD5_K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+// one block for each of N lanes
+fn md5_compress<const N: usize>(state: &mut [[u32; N]; 4], blocks: &[[u8; 64]; N]) {
+    let mut m = [[0; N]; 16];
+    for (lane, block) in blocks.iter().enumerate() {
+        for (w, c) in block.chunks_exact(4).enumerate() {
+            m[w][lane] = u32::from_le_bytes(c.try_into().unwrap());
+        }
+    }
+    let [mut a, mut b, mut c, mut d] = *state;
+    for i in 0..64 {
+        let (g, shift) = match i / 16 {
+            0 => (i, MD5_SHIFTS[i % 4]),
+            1 => ((5 * i + 1) % 16, MD5_SHIFTS[4 + i % 4]),
+            2 => ((3 * i + 5) % 16, MD5_SHIFTS[8 + i % 4]),
+            _ => ((7 * i) % 16, MD5_SHIFTS[12 + i % 4]),
+        };
+        for l in 0..N {
+            let f = match i / 16 {
+                0 => (b[l] & c[l]) | (!b[l] & d[l]),
+                1 => (d[l] & b[l]) | (!d[l] & c[l]),
+                2 => b[l] ^ c[l] ^ d[l],
+                _ => c[l] ^ (b[l] | !d[l]),
+            };
+            let rotated = a[l]
+                .wrapping_add(f)
+                .wrapping_add(MD5_K[i])
+                .wrapping_add(m[g][l])
+                .rotate_left(shift);
+            a[l] = d[l];
+            d[l] = c[l];
+            c[l] = b[l];
+            b[l] = b[l].wrapping_add(rotated);
+        }
+    }
+    for (s, v) in state.iter_mut().zip([a, b, c, d]) {
+        for l in 0..N {
+            s[l] = s[l].wrapping_add(v[l]);
+        }
+    }
+}
+
+// The MD5 state after the whole blocks of a prefix, ready to hash the prefix followed by any short
+// suffix.
+pub struct Md5Prefix {
+    state: [u32; 4],
+    tail: Vec<u8>, // what's left of the prefix after its whole blocks
+    len: u64,
+}
+
+impl Md5Prefix {
+    pub fn new(prefix: &[u8]) -> Self {
+        let mut state = MD5_INIT.map(|s| [s]);
+        let mut blocks = prefix.chunks_exact(64);
+        for block in blocks.by_ref() {
+            md5_compress(&mut state, &[block.try_into().unwrap()]);
+        }
+        Self {
+            state: state.map(|[s]| s),
+            tail: blocks.remainder().to_vec(),
+            len: prefix.len() as u64,
+        }
+    }
+
+    // Fills blocks with the padded end of the message, returning how many there are (1 or 2).
+    // The suffix can be at most 55 bytes.
+    fn final_blocks(&self, suffix: &[u8], blocks: &mut [[u8; 64]; 2]) -> usize {
+        assert!(suffix.len() <= 55, "suffix too long");
+        let buf = blocks.as_flattened_mut();
+        let end = self.tail.len() + suffix.len();
+        buf[..self.tail.len()].copy_from_slice(&self.tail);
+        buf[self.tail.len()..end].copy_from_slice(suffix);
+        let n = if end < 56 { 1 } else { 2 };
+        buf[end] = 0x80;
+        buf[end + 1..n * 64 - 8].fill(0);
+        let bits = (self.len + suffix.len() as u64) * 8;
+        buf[n * 64 - 8..n * 64].copy_from_slice(&bits.to_le_bytes());
+        n
+    }
+
+    pub fn digest(&self, suffix: &[u8]) -> [u8; 16] {
+        let mut blocks = [[0; 64]; 2];
+        let n = self.final_blocks(suffix, &mut blocks);
+        let mut state = self.state.map(|s| [s]);
+        for block in &blocks[..n] {
+            md5_compress(&mut state, &[*block]);
+        }
+        md5_output(state.map(|[s]| s))
+    }
+
+    // digests for N suffixes at once
+    fn digest_lanes<const N: usize>(&self, suffixes: [&[u8]; N]) -> [[u8; 16]; N] {
+        let mut blocks = [[[0; 64]; 2]; N];
+        let counts =
+            std::array::from_fn::<_, N, _>(|l| self.final_blocks(suffixes[l], &mut blocks[l]));
+        if counts.iter().any(|&n| n != counts[0]) {
+            // some need an extra block
+            return std::array::from_fn(|l| self.digest(suffixes[l]));
+        }
+        let mut state = self.state.map(|s| [s; N]);
+        // blocks by position rather than by lane
+        let blocks: [[[u8; 64]; N]; 2] =
+            std::array::from_fn(|i| std::array::from_fn(|l| blocks[l][i]));
+        for lane_blocks in &blocks[..counts[0]] {
+            md5_compress(&mut state, lane_blocks);
+        }
+        std::array::from_fn(|l| md5_output(state.map(|s| s[l])))
+    }
+}
+
+fn md5_output(state: [u32; 4]) -> [u8; 16] {
+    let mut digest = [0; 16];
+    for (out, s) in digest.chunks_exact_mut(4).zip(state) {
+        out.copy_from_slice(&s.to_le_bytes());
+    }
+    digest
+}
+
+// A number as ASCII decimal digits, right-aligned in a buffer so it can grow without allocating
+#[derive(Clone, Copy)]
+pub struct Decimal {
+    digits: [u8; 20], // enough for any u64
+    start: usize,
+    value: u64,
+}
+
+impl Decimal {
+    pub fn new(value: u64) -> Self {
+        let mut d = Self {
+            digits: [b'0'; 20],
+            start: 19,
+            value: 0,
+        };
+        d.add(value);
+        d
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.digits[self.start..]
+    }
+
+    // only touches the digits that change
+    pub fn add(&mut self, n: u64) {
+        self.value += n;
+        let mut carry = n;
+        let mut i = self.digits.len();
+        while carry > 0 {
+            i -= 1;
+            let digit = (self.digits[i] - b'0') as u64 + carry % 10;
+            carry = carry / 10 + digit / 10;
+            self.digits[i] = b'0' + (digit % 10) as u8;
+        }
+        self.start = self.start.min(i);
+    }
+}
+
+// Md5's numbered digests, N at a time
+struct Md5Numbered<const N: usize> {
+    prefix: Md5Prefix,
+    nums: [Decimal; N],
+    step: u64, // for each lane
+    done: [(u64, [u8; 16]); N],
+    next: usize, // index into done
+}
+
+impl<const N: usize> Md5Numbered<N> {
+    fn new(key: &[u8], start: u64, step: u64) -> Self {
+        Self {
+            prefix: Md5Prefix::new(key),
+            nums: std::array::from_fn(|l| Decimal::new(start + l as u64 * step)),
+            step: step * N as u64,
+            done: [(0, [0; 16]); N],
+            next: N,
+        }
+    }
+}
+
+impl<const N: usize> Iterator for Md5Numbered<N> {
+    type Item = (u64, [u8; 16]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == N {
+            let digests = self
+                .prefix
+                .digest_lanes(self.nums.each_ref().map(|n| n.as_bytes()));
+            for (l, digest) in digests.into_iter().enumerate() {
+                self.done[l] = (self.nums[l].value(), digest);
+                self.nums[l].add(self.step);
+            }
+            self.next = 0;
+        }
+        self.next += 1;
+        Some(self.done[self.next - 1])
+    }
+}
+
+pub fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// SHA-1 and SHA-256 share their padding: a 1 bit, zeros, then the message length in bits as a
+// big-endian u64, making a whole number of 64-byte blocks.
+fn padded_blocks(data: &[u8]) -> impl Iterator<Item = [u8; 64]> + '_ {
+    let full = data.len() / 64;
+    let mut tail = data[full * 64..].to_vec();
+    tail.push(0x80);
+    while tail.len() % 64 != 56 {
+        tail.push(0);
+    }
+    tail.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+    data.chunks_exact(64).map(|b| b.try_into().unwrap()).chain(
+        tail.chunks_exact(64)
+            .map(|b| b.try_into().unwrap())
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn be_words<const N: usize>(block: &[u8; 64]) -> [u32; N] {
+    let mut w = [0; N];
+    for (i, c) in block.chunks_exact(4).enumerate() {
+        w[i] = u32::from_be_bytes(c.try_into().unwrap());
+    }
+    w
+}
+
+pub struct Sha1;
+
+impl HashFunction for Sha1 {
+    type Digest = [u8; 20];
+    const NAME: &'static str = "sha1";
+
+    fn digest(data: &[u8]) -> [u8; 20] {
+        let mut h: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+        for block in padded_blocks(data) {
+            let mut w: [u32; 80] = be_words(&block);
+            for i in 16..80 {
+                w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+            }
+            let [mut a, mut b, mut c, mut d, mut e] = h;
+            for (i, wi) in w.iter().enumerate() {
+                let (f, k) = match i {
+                    0..=19 => ((b & c) | (!b & d), 0x5a827999),
+                    20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+                    40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                    _ => (b ^ c ^ d, 0xca62c1d6),
+                };
+                let t = a
+                    .rotate_left(5)
+                    .wrapping_add(f)
+                    .wrapping_add(e)
+                    .wrapping_add(k)
+                    .wrapping_add(*wi);
+                e = d;
+                d = c;
+                c = b.rotate_left(30);
+                b = a;
+                a = t;
+            }
+            for (h, v) in h.iter_mut().zip([a, b, c, d, e]) {
+                *h = h.wrapping_add(v);
+            }
+        }
+        let mut digest = [0; 20];
+        for (out, h) in digest.chunks_exact_mut(4).zip(h) {
+            out.copy_from_slice(&h.to_be_bytes());
+        }
+        digest
+    }
+}
+
+pub struct Sha256;
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+impl HashFunction for Sha256 {
+    type Digest = [u8; 32];
+    const NAME: &'static str = "sha256";
+
+    fn digest(data: &[u8]) -> [u8; 32] {
+        let mut h: [u32; 8] = [
+            0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+            0x5be0cd19,
+        ];
+        for block in padded_blocks(data) {
+            let mut w: [u32; 64] = be_words(&block);
+            for i in 16..64 {
+                let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+                let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+                w[i] = w[i - 16]
+                    .wrapping_add(s0)
+                    .wrapping_add(w[i - 7])
+                    .wrapping_add(s1);
+            }
+            let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+            for (k, wi) in SHA256_K.iter().zip(w) {
+                let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+                let ch = (e & f) ^ (!e & g);
+                let t1 = hh
+                    .wrapping_add(s1)
+                    .wrapping_add(ch)
+                    .wrapping_add(*k)
+                    .wrapping_add(wi);
+                let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+                let maj = (a & b) ^ (a & c) ^ (b & c);
+                let t2 = s0.wrapping_add(maj);
+                hh = g;
+                g = f;
+                f = e;
+                e = d.wrapping_add(t1);
+                d = c;
+                c = b;
+                b = a;
+                a = t1.wrapping_add(t2);
+            }
+            for (h, v) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+                *h = h.wrapping_add(v);
+            }
+        }
+        let mut digest = [0; 32];
+        for (out, h) in digest.chunks_exact_mut(4).zip(h) {
+            out.copy_from_slice(&h.to_be_bytes());
+        }
+        digest
+    }
+}
+
+#[test]
+fn test() {
+    // test vectors from RFC 1321 and FIPS 180
+    let million_a = vec![b'a'; 1_000_000];
+    let inputs: [&[u8]; 5] = [
+        b"",
+        b"abc",
+        b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+        b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+        &million_a,
+    ];
+    let md5 = [
+        "d41d8cd98f00b204e9800998ecf8427e",
+        "900150983cd24fb0d6963f7d28e17f72",
+        "8215ef0796a20bcaaae116d3876c664a",
+        "57edf4a22be3c955ac49da2e2107b67a",
+        "7707d6ae4e027c70eea2a935c2296f21",
+    ];
+    let sha1 = [
+        "da39a3ee5e6b4b0d3255bfef95601890afd80709",
+        "a9993e364706816aba3e25717850c26c9cd0d89d",
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+        "50abf5706a150990a08b2c5ea40fa0e585554732",
+        "34aa973cd4c4daa4f61eeb2bdbad27316534016f",
+    ];
+    let sha256 = [
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        "f371bc4a311f2b009eef952dd83ca80e2b60026c8e935592d0f9c308453c813e",
+        "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0",
+    ];
+    for (i, input) in inputs.iter().enumerate() {
+        assert_eq!(md5[i], hex(&Md5::digest(input)));
+        if input.len() < 100 {
+            assert_eq!(md5[i], hex(&Md5Prefix::new(input).digest(b"")));
+        }
+        assert_eq!(sha1[i], hex(&Sha1::digest(input)));
+        assert_eq!(sha256[i], hex(&Sha256::digest(input)));
+    }
+    // padding spills into a second block
+    assert_eq!(
+        "c2db330f6083854c99d4b5bfb6e8f29f201be699",
+        hex(&Sha1::digest(&[b'a'; 56]))
+    );
+    assert_eq!(
+        "b35439a4ac6f0948b6d6f9e3c6af0f5f590ce20f1bde7090ef7970686ec6738a",
+        hex(&Sha256::digest(&[b'a'; 56]))
+    );
+}
+
+#[test]
+fn test_md5_prefix() {
+    let key: Vec<u8> = (0..200).map(|i| b'a' + i % 26).collect();
+    for key_len in [0, 1, 8, 55, 56, 63, 64, 65, 120, 128, 200] {
+        let key = &key[..key_len];
+        let prefix = Md5Prefix::new(key);
+        for suffix in [&b""[..], b"7", b"12345678901234567890", &[b'x'; 55]] {
+            let message = [key, suffix].concat();
+            assert_eq!(Md5::digest(&message), prefix.digest(suffix));
+        }
+        // numbers across a change in length, in lanes
+        for (num, digest) in Md5::numbered(key, 95, 3).take(20) {
+            assert_eq!(
+                Md5::digest(&[key, num.to_string().as_bytes()].concat()),
+                digest
+            );
+        }
+        let nums: Vec<u64> = Md5::numbered(key, 95, 3).take(20).map(|(n, _)| n).collect();
+        assert_eq!((0..20).map(|i| 95 + 3 * i).collect::<Vec<_>>(), nums);
+    }
+
+    let mut d = Decimal::new(0);
+    assert_eq!(b"0", d.as_bytes());
+    d.add(998);
+    d.add(7);
+    assert_eq!((b"1005".as_slice(), 1005), (d.as_bytes(), d.value()));
+    d.add(u64::MAX - 1005);
+    assert_eq!(u64::MAX.to_string().as_bytes(), d.as_bytes());
+}
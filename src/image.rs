@@ -0,0 +1,119 @@
+// Minimal bitmap images written as binary PPM (colour) or PGM (greyscale), which most image
+// viewers and converters understand.
+#![allow(dead_code)]
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+pub type Rgb = [u8; 3];
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Rgb>, // row by row from the top left
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, colour: Rgb) {
+        self.pixels[y * self.width + x] = colour;
+    }
+
+    pub fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, colour: Rgb) {
+        for y in y..(y + height).min(self.height) {
+            for x in x..(x + width).min(self.width) {
+                self.set(x, y, colour);
+            }
+        }
+    }
+
+    pub fn write_ppm(&self, mut w: impl Write) -> io::Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for p in &self.pixels {
+            w.write_all(p)?;
+        }
+        Ok(())
+    }
+
+    // pixels are converted to greyscale by their luma
+    pub fn write_pgm(&self, mut w: impl Write) -> io::Result<()> {
+        write!(w, "P5\n{} {}\n255\n", self.width, self.height)?;
+        for p in &self.pixels {
+            w.write_all(&[luma(*p)])?;
+        }
+        Ok(())
+    }
+
+    pub fn save_ppm(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_ppm(&mut w)?;
+        w.flush()
+    }
+
+    pub fn save_pgm(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_pgm(&mut w)?;
+        w.flush()
+    }
+}
+
+pub fn grey(level: u8) -> Rgb {
+    [level; 3]
+}
+
+fn luma(p: Rgb) -> u8 {
+    ((p[0] as u32 * 299 + p[1] as u32 * 587 + p[2] as u32 * 114) / 1000) as u8
+}
+
+// colour scaled towards black; brightness is 0.0 to 1.0
+pub fn dim(colour: Rgb, brightness: f64) -> Rgb {
+    colour.map(|c| (c as f64 * brightness.clamp(0.0, 1.0)).round() as u8)
+}
+
+// distinct colours for telling things apart, repeating if there are lots of things
+pub fn palette(i: usize) -> Rgb {
+    const COLOURS: [Rgb; 8] = [
+        [230, 25, 75],
+        [60, 180, 75],
+        [255, 225, 25],
+        [0, 130, 200],
+        [245, 130, 48],
+        [145, 30, 180],
+        [70, 240, 240],
+        [240, 50, 230],
+    ];
+    COLOURS[i % COLOURS.len()]
+}
+
+#[test]
+fn test() {
+    let mut image = Image::new(3, 2, grey(0));
+    image.set(0, 0, [255, 0, 0]);
+    image.fill(1, 1, 5, 5, grey(200));
+    assert_eq!(grey(200), image.get(2, 1));
+    assert_eq!(grey(0), image.get(0, 1));
+
+    let mut ppm = vec![];
+    image.write_ppm(&mut ppm).unwrap();
+    assert_eq!(b"P6\n3 2\n255\n", &ppm[..11]);
+    assert_eq!(11 + 3 * 6, ppm.len());
+    assert_eq!([255, 0, 0], ppm[11..14]);
+
+    let mut pgm = vec![];
+    image.write_pgm(&mut pgm).unwrap();
+    assert_eq!(b"P5\n3 2\n255\n\x4c\0\0\0\xc8\xc8".to_vec(), pgm);
+
+    assert_eq!([115, 13, 38], dim(palette(0), 0.5));
+}
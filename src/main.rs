@@ -27,6 +27,14 @@ mod day6;
 mod day7;
 mod day8;
 mod day9;
+mod hash;
+mod image;
+mod parallel;
+mod parse;
+#[cfg(test)]
+mod rng;
+mod sat;
+mod search;
 
 #[derive(Eq, PartialEq)]
 pub enum Part {
@@ -0,0 +1,193 @@
+// Searching for the smallest index that satisfies some condition, using every CPU.
+//
+// Threads claim chunks of indices in order from a shared counter and scan them.  When one finds a
+// match it lowers a shared bound.  Chunks claimed after that are cut off at the bound and those
+// starting past it are never claimed, but a chunk already being checked is checked to its end, so
+// work past the bound can go on for up to a chunk per thread.  Once they've all stopped the lowest
+// match found is the lowest one there is.
+#![allow(dead_code)]
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How much work one thread did
+#[derive(Debug)]
+pub struct WorkerStats {
+    pub items: u64,
+    pub elapsed: Duration,
+}
+
+impl WorkerStats {
+    pub fn per_second(&self) -> f64 {
+        self.items as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+}
+
+// The smallest index in range for which pred is true
+pub fn find_index(range: Range<u64>, chunk: u64, pred: impl Fn(u64) -> bool + Sync) -> Option<u64> {
+    find_first(range, chunk, |r| {
+        r.into_iter().find(|&i| pred(i)).map(|i| (i, ()))
+    })
+    .map(|(i, _)| i)
+}
+
+// check is given a chunk of indices and returns the first match in it (with anything else worth
+// keeping), if there is one
+pub fn find_first<T: Send>(
+    range: Range<u64>,
+    chunk: u64,
+    check: impl Fn(Range<u64>) -> Option<(u64, T)> + Sync,
+) -> Option<(u64, T)> {
+    find_first_with_progress(range, chunk, check, Duration::MAX, |_, _| {}).0
+}
+
+// As find_first, but every interval progress is called with the index below which everything has
+// been checked and each thread's stats so far.  It's called once more at the end.
+pub fn find_first_with_progress<T: Send>(
+    range: Range<u64>,
+    chunk: u64,
+    check: impl Fn(Range<u64>) -> Option<(u64, T)> + Sync,
+    interval: Duration,
+    mut progress: impl FnMut(u64, &[WorkerStats]),
+) -> (Option<(u64, T)>, Vec<WorkerStats>) {
+    assert!(chunk > 0);
+    let threads = num_cpus::get();
+    // start of the next chunk to be claimed
+    let next = AtomicU64::new(range.start);
+    // nothing at or above this can be the answer
+    let bound = AtomicU64::new(range.end);
+    // for each thread, the start of the chunk it's checking (u64::MAX when it's not) and how many
+    // indices it has checked
+    let working: Vec<_> = (0..threads).map(|_| AtomicU64::new(u64::MAX)).collect();
+    let items: Vec<_> = (0..threads).map(|_| AtomicU64::new(0)).collect();
+    let found = Mutex::new(None);
+    let t0 = Instant::now();
+
+    let checked_below = || {
+        let claimed = next
+            .load(Ordering::SeqCst)
+            .min(bound.load(Ordering::SeqCst));
+        working
+            .iter()
+            .map(|w| w.load(Ordering::SeqCst))
+            .fold(claimed, u64::min)
+    };
+    let stats = |elapsed: &[Duration]| {
+        items
+            .iter()
+            .zip(elapsed)
+            .map(|(n, &elapsed)| WorkerStats {
+                items: n.load(Ordering::Relaxed),
+                elapsed,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let elapsed = thread::scope(|s| {
+        // never sent on; the receiver sees it disconnect when every thread has finished
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        let handles: Vec<_> = (0..threads)
+            .map(|w| {
+                let done_tx = done_tx.clone();
+                let (next, bound, working, items, found) =
+                    (&next, &bound, &working, &items, &found);
+                let check = &check;
+                s.spawn(move || {
+                    loop {
+                        // until the chunk is claimed, it's covered by next; mark this thread as
+                        // working from no later than it first, so no part of it is ever
+                        // reported as checked too soon
+                        working[w].store(next.load(Ordering::SeqCst), Ordering::SeqCst);
+                        let lo = next
+                            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                                Some(n.saturating_add(chunk))
+                            })
+                            .unwrap();
+                        let hi = lo.saturating_add(chunk).min(bound.load(Ordering::SeqCst));
+                        if lo >= hi {
+                            break;
+                        }
+                        working[w].store(lo, Ordering::SeqCst);
+                        match check(lo..hi) {
+                            Some((i, value)) => {
+                                items[w].fetch_add(i - lo + 1, Ordering::Relaxed);
+                                bound.fetch_min(i, Ordering::SeqCst);
+                                let mut found = found.lock().unwrap();
+                                if found.as_ref().is_none_or(|&(j, _)| i < j) {
+                                    *found = Some((i, value));
+                                }
+                            }
+                            None => {
+                                items[w].fetch_add(hi - lo, Ordering::Relaxed);
+                            }
+                        }
+                    }
+                    working[w].store(u64::MAX, Ordering::SeqCst);
+                    drop(done_tx);
+                    t0.elapsed()
+                })
+            })
+            .collect();
+        drop(done_tx);
+
+        while let Err(mpsc::RecvTimeoutError::Timeout) = done_rx.recv_timeout(interval) {
+            let elapsed = t0.elapsed();
+            progress(checked_below(), &stats(&vec![elapsed; threads]));
+        }
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let found = found.into_inner().unwrap();
+    let stats = stats(&elapsed);
+    let end = found.as_ref().map_or(range.end, |&(i, _)| i);
+    progress(end, &stats);
+    (found, stats)
+}
+
+#[test]
+fn test() {
+    // few matches, in many chunks
+    for chunk in [1, 7, 1000] {
+        assert_eq!(
+            Some(99_991),
+            find_index(5..200_000, chunk, |i| i % 99_991 == 0)
+        );
+        assert_eq!(None, find_index(5..200_000, chunk, |i| i == 3));
+        assert_eq!(
+            Some(u64::MAX - 49),
+            find_index(u64::MAX - 50..u64::MAX, chunk, |i| i % 2 == 0)
+        );
+    }
+    // lots of matches, so threads race to find them
+    for start in [0, 1, 12345] {
+        assert_eq!(
+            Some((start + 3 - start % 3, (start + 3 - start % 3) * 2)),
+            find_first(start + 1..u64::MAX, 16, |r| {
+                r.into_iter().find(|i| i % 3 == 0).map(|i| (i, i * 2))
+            })
+        );
+    }
+
+    let mut reports = vec![];
+    let (found, stats) = find_first_with_progress(
+        0..1_000_000,
+        100,
+        |r| r.into_iter().find(|&i| i == 654_321).map(|i| (i, ())),
+        Duration::from_micros(100),
+        |checked_below, stats| reports.push((checked_below, stats.iter().map(|s| s.items).sum())),
+    );
+    assert_eq!(Some((654_321, ())), found);
+    assert!(stats.iter().map(|s| s.items).sum::<u64>() >= 654_322);
+    assert_eq!(654_321, reports.last().unwrap().0);
+    // never claims to have checked more than it has
+    assert!(reports.windows(2).all(|w| w[0].0 <= w[1].0));
+    assert!(reports
+        .iter()
+        .all(|&(below, items): &(u64, u64)| below <= items));
+}
@@ -0,0 +1,296 @@
+// A small toolkit for parsing puzzle input.
+//
+// A Parser walks through one line, consuming pieces (literal text, keywords, integers, names)
+// and returning them as typed values.  Anything unexpected becomes a ParseError giving the line
+// and column it happened at.  lines() runs a parser over every line of the input, and
+// definitions() over every line of a file of definitions.
+#![allow(dead_code)]
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,   // 1-based, or 0 if the error is about the whole input
+    pub column: usize, // 1-based, or 0 along with line
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}", self.message);
+        }
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub struct Parser<'a> {
+    input: &'a str,
+    pos: usize, // byte offset into input
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self::on_line(input, 1)
+    }
+
+    pub fn on_line(input: &'a str, line: usize) -> Self {
+        Self {
+            input,
+            pos: 0,
+            line,
+        }
+    }
+
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        self.error_at(self.pos, message)
+    }
+
+    // pos is a byte offset previously returned by position()
+    pub fn error_at(&self, pos: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.input[..pos].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    // everything not yet consumed
+    pub fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.pos == self.input.len()
+    }
+
+    pub fn end(&self) -> Result<(), ParseError> {
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err(self.error(format!("unexpected {:?}", self.rest())))
+        }
+    }
+
+    pub fn try_literal(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn literal(&mut self, s: &str) -> Result<(), ParseError> {
+        if self.try_literal(s) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {:?}", s)))
+        }
+    }
+
+    // one of several keywords, each mapped to a value.  The longest match wins.
+    pub fn keyword<T: Clone>(&mut self, choices: &[(&str, T)]) -> Result<T, ParseError> {
+        let best = choices
+            .iter()
+            .filter(|(k, _)| self.rest().starts_with(k))
+            .max_by_key(|(k, _)| k.len());
+        match best {
+            Some((k, v)) => {
+                self.pos += k.len();
+                Ok(v.clone())
+            }
+            None => Err(self.error(format!(
+                "expected one of {}",
+                choices
+                    .iter()
+                    .map(|(k, _)| format!("{:?}", k))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
+
+    // consume characters while they match; may be empty
+    pub fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|c| !f(c))
+            .unwrap_or_else(|| self.rest().len());
+        self.pos += len;
+        &self.input[start..self.pos]
+    }
+
+    // consume everything up to (but not including) the delimiter
+    pub fn until(&mut self, delimiter: &str) -> Result<&'a str, ParseError> {
+        match self.rest().find(delimiter) {
+            Some(len) => {
+                let start = self.pos;
+                self.pos += len;
+                Ok(&self.input[start..self.pos])
+            }
+            None => Err(self.error(format!("expected {:?}", delimiter))),
+        }
+    }
+
+    // a run of non-whitespace characters
+    pub fn token(&mut self) -> Result<&'a str, ParseError> {
+        let token = self.take_while(|c| !c.is_whitespace());
+        if token.is_empty() {
+            Err(self.error("expected a token"))
+        } else {
+            Ok(token)
+        }
+    }
+
+    // a run of letters, digits and underscores
+    pub fn word(&mut self) -> Result<&'a str, ParseError> {
+        let word = self.take_while(|c| c.is_alphanumeric() || c == '_');
+        if word.is_empty() {
+            Err(self.error("expected a word"))
+        } else {
+            Ok(word)
+        }
+    }
+
+    // a word that starts with a letter or underscore
+    pub fn ident(&mut self) -> Result<&'a str, ParseError> {
+        match self.rest().chars().next() {
+            Some(c) if c.is_alphabetic() || c == '_' => self.word(),
+            _ => Err(self.error("expected a name")),
+        }
+    }
+
+    // an optionally signed decimal integer
+    pub fn integer<T: FromStr>(&mut self) -> Result<T, ParseError> {
+        let start = self.pos;
+        if !self.try_literal("-") {
+            self.try_literal("+");
+        }
+        if self.take_while(|c| c.is_ascii_digit()).is_empty() {
+            let e = self.error("expected a number");
+            self.pos = start;
+            return Err(e);
+        }
+        self.input[start..self.pos]
+            .trim_start_matches('+')
+            .parse()
+            .map_err(|_| self.error_at(start, "number out of range"))
+    }
+
+    // one or more items with a separator between each
+    pub fn separated<T>(
+        &mut self,
+        separator: &str,
+        mut item: impl FnMut(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut items = vec![item(self)?];
+        while self.try_literal(separator) {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+}
+
+// Parse every line of input with f, which must consume the whole line.
+pub fn lines<'a, T>(
+    input: &'a str,
+    mut f: impl FnMut(&mut Parser<'a>) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let mut p = Parser::on_line(line, i + 1);
+            let value = f(&mut p)?;
+            p.end()?;
+            Ok(value)
+        })
+        .collect()
+}
+
+// Parse every line of a file of definitions with f, except blank lines and those starting with #
+// (after any spaces).  A file without any definitions is an error saying there are no `what`.
+pub fn definitions<'a, T>(
+    input: &'a str,
+    what: &str,
+    mut f: impl FnMut(&mut Parser<'a>) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    let definitions: Vec<T> = lines(input, |p| {
+        let rest = p.rest().trim_start();
+        if rest.is_empty() || rest.starts_with('#') {
+            p.take_while(|_| true);
+            return Ok(None);
+        }
+        f(p).map(Some)
+    })?
+    .into_iter()
+    .flatten()
+    .collect();
+    if definitions.is_empty() {
+        return Err(ParseError {
+            line: 0,
+            column: 0,
+            message: format!("no {}", what),
+        });
+    }
+    Ok(definitions)
+}
+
+#[test]
+fn test() {
+    let mut p = Parser::new("move -12 to x_1, y, z!");
+    assert_eq!(Ok("move"), p.ident());
+    p.literal(" ").unwrap();
+    assert_eq!(Ok(-12), p.integer::<i32>());
+    assert_eq!(Ok(2), p.keyword(&[(" t", 1), (" to ", 2)]));
+    assert_eq!(Ok(vec!["x_1", "y", "z"]), p.separated(", ", |p| p.ident()));
+    assert_eq!("!", p.rest());
+    assert_eq!(
+        Err(ParseError {
+            line: 1,
+            column: 22,
+            message: "unexpected \"!\"".to_string()
+        }),
+        p.end()
+    );
+
+    assert_eq!(Ok(vec![(3, 4), (-5, 6)]), lines("3,4\n-5,+6\n", pair));
+    let e = lines("3,4\n5;6\n", pair).unwrap_err();
+    assert_eq!((2, 2), (e.line, e.column));
+    assert_eq!("line 2, column 2: expected \",\"", e.to_string());
+
+    let e = lines("300,1\n", |p| p.integer::<u8>()).unwrap_err();
+    assert_eq!("line 1, column 1: number out of range", e.to_string());
+
+    assert_eq!(
+        Ok(vec![(1, 2), (3, 4)]),
+        definitions("# pairs\n1,2\n\n  # more\n3,4\n", "pairs", pair)
+    );
+    let e = definitions("\n# nothing\n", "pairs", pair).unwrap_err();
+    assert_eq!("no pairs", e.to_string());
+
+    let mut p = Parser::new("Hit Points: 12");
+    assert_eq!(Ok("Hit Points"), p.until(": "));
+    assert!(p.ident().is_err());
+}
+
+#[cfg(test)]
+fn pair(p: &mut Parser) -> Result<(i32, i32), ParseError> {
+    let x = p.integer()?;
+    p.literal(",")?;
+    Ok((x, p.integer()?))
+}
@@ -0,0 +1,18 @@
+// A small xorshift random number generator, so tests can make the same random inputs every run
+pub struct Rng(u64);
+
+impl Rng {
+    // seed mustn't be 0, which xorshift never leaves
+    pub fn new(seed: u64) -> Self {
+        assert!(seed != 0, "seed must not be 0");
+        Rng(seed)
+    }
+
+    // a number from 0 to n - 1
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
@@ -0,0 +1,403 @@
+// A small CDCL SAT solver: clauses are watched by two literals for unit propagation, each
+// conflict is analysed back to its first unique implication point to learn a clause and jump back,
+// variables in recent conflicts are tried first, and searches restart on the Luby sequence.
+// Clauses can be added between solves, for example to rule out the solutions found so far.
+#![allow(dead_code)]
+
+use std::ops::Not;
+
+// Variable v is Lit(2v) and its negation Lit(2v + 1)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Lit(u32);
+
+impl Lit {
+    pub fn new(var: usize, positive: bool) -> Self {
+        Lit(2 * var as u32 + !positive as u32)
+    }
+
+    pub fn var(self) -> usize {
+        (self.0 / 2) as usize
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0.is_multiple_of(2)
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Not for Lit {
+    type Output = Lit;
+
+    fn not(self) -> Lit {
+        Lit(self.0 ^ 1)
+    }
+}
+
+#[derive(Default)]
+pub struct Solver {
+    clauses: Vec<Vec<Lit>>,   // the first two literals of each are watched
+    watches: Vec<Vec<usize>>, // by literal: the clauses watching it
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,          // decision level each variable was set at
+    reasons: Vec<Option<usize>>, // the clause that set it, unless it was a decision
+    trail: Vec<Lit>,             // literals made true, in order
+    level_starts: Vec<usize>,    // where each decision level starts in trail
+    propagated: usize,           // trail before this has been propagated
+    activity: Vec<f64>,
+    bump: f64,
+    phases: Vec<bool>, // the value each variable last had
+    unsat: bool,
+    pub conflicts: u64,
+    pub decisions: u64,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self {
+            bump: 1.0,
+            ..Default::default()
+        }
+    }
+
+    pub fn vars(&self) -> usize {
+        self.values.len()
+    }
+
+    // a new variable, as its positive literal
+    pub fn new_var(&mut self) -> Lit {
+        let var = self.vars();
+        self.values.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        self.activity.push(0.0);
+        self.phases.push(false);
+        self.watches.push(vec![]);
+        self.watches.push(vec![]);
+        Lit::new(var, true)
+    }
+
+    pub fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.var()].map(|v| v == lit.is_positive())
+    }
+
+    // At least one of lits must be true.  Returns false if that makes the clauses unsatisfiable
+    // without any searching (an empty clause always does).
+    pub fn add_clause(&mut self, lits: &[Lit]) -> bool {
+        self.backtrack(0);
+        let mut lits = lits.to_vec();
+        lits.sort_unstable();
+        lits.dedup();
+        if lits.windows(2).any(|w| w[1] == !w[0]) {
+            return !self.unsat; // always true
+        }
+        if lits.iter().any(|&l| self.value(l) == Some(true)) {
+            return !self.unsat;
+        }
+        lits.retain(|&l| self.value(l).is_none());
+        match lits.len() {
+            0 => self.unsat = true,
+            1 => {
+                self.assign(lits[0], None);
+                if self.propagate().is_some() {
+                    self.unsat = true;
+                }
+            }
+            _ => {
+                self.attach(lits);
+            }
+        }
+        !self.unsat
+    }
+
+    // A value for every variable satisfying every clause, or None if there isn't one
+    pub fn solve(&mut self) -> Option<Vec<bool>> {
+        if self.unsat {
+            return None;
+        }
+        self.backtrack(0);
+        for restart in 1.. {
+            let mut conflicts_left = 100 * luby(restart);
+            loop {
+                if let Some(conflict) = self.propagate() {
+                    self.conflicts += 1;
+                    if self.level_starts.is_empty() {
+                        self.unsat = true;
+                        return None;
+                    }
+                    let (learnt, level) = self.analyze(conflict);
+                    self.backtrack(level);
+                    if learnt.len() == 1 {
+                        self.assign(learnt[0], None);
+                    } else {
+                        let c = self.attach(learnt);
+                        self.assign(self.clauses[c][0], Some(c));
+                    }
+                    self.bump /= 0.95;
+                    conflicts_left = conflicts_left.saturating_sub(1);
+                } else if conflicts_left == 0 {
+                    self.backtrack(0);
+                    break;
+                } else {
+                    match self.pick() {
+                        Some(var) => {
+                            self.decisions += 1;
+                            self.level_starts.push(self.trail.len());
+                            self.assign(Lit::new(var, self.phases[var]), None);
+                        }
+                        None => return Some(self.values.iter().map(|v| v.unwrap()).collect()),
+                    }
+                }
+            }
+        }
+        unreachable!()
+    }
+
+    fn attach(&mut self, lits: Vec<Lit>) -> usize {
+        let c = self.clauses.len();
+        self.watches[lits[0].index()].push(c);
+        self.watches[lits[1].index()].push(c);
+        self.clauses.push(lits);
+        c
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var();
+        self.values[var] = Some(lit.is_positive());
+        self.levels[var] = self.level_starts.len();
+        self.reasons[var] = reason;
+        self.trail.push(lit);
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.level_starts.len() <= level {
+            return;
+        }
+        let start = self.level_starts[level];
+        for lit in self.trail.drain(start..) {
+            let var = lit.var();
+            self.phases[var] = lit.is_positive();
+            self.values[var] = None;
+            self.reasons[var] = None;
+        }
+        self.level_starts.truncate(level);
+        self.propagated = self.trail.len();
+    }
+
+    // Makes every clause with only one literal left that isn't false make it true.  Returns a
+    // clause that's all false if there is one.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let false_lit = !self.trail[self.propagated];
+            self.propagated += 1;
+            let mut watching = std::mem::take(&mut self.watches[false_lit.index()]);
+            let mut i = 0;
+            while i < watching.len() {
+                let c = watching[i];
+                let values = &self.values;
+                let value = |l: Lit| values[l.var()].map(|v| v == l.is_positive());
+                let clause = &mut self.clauses[c];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                if value(clause[0]) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+                // watch another literal that isn't false, if there is one
+                if let Some(k) = (2..clause.len()).find(|&k| value(clause[k]) != Some(false)) {
+                    clause.swap(1, k);
+                    self.watches[clause[1].index()].push(c);
+                    watching.swap_remove(i);
+                    continue;
+                }
+                let other = clause[0];
+                if value(other) == Some(false) {
+                    self.watches[false_lit.index()] = watching;
+                    self.propagated = self.trail.len();
+                    return Some(c);
+                }
+                self.assign(other, Some(c));
+                i += 1;
+            }
+            self.watches[false_lit.index()] = watching;
+        }
+        None
+    }
+
+    // Resolves the conflict with the reasons for its literals set at the current level until
+    // only one is left.  Returns the learnt clause, with that literal (negated) first and one
+    // from the next highest level second, and the level to jump back to.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let level = self.level_starts.len();
+        let mut seen = vec![false; self.vars()];
+        let mut learnt = vec![Lit(0)];
+        let mut at_level = 0;
+        let mut clause = conflict;
+        let mut index = self.trail.len();
+        let mut skip_first = false;
+        loop {
+            for k in skip_first as usize..self.clauses[clause].len() {
+                let q = self.clauses[clause][k];
+                let var = q.var();
+                if !seen[var] && self.levels[var] > 0 {
+                    seen[var] = true;
+                    self.bump_activity(var);
+                    if self.levels[var] == level {
+                        at_level += 1;
+                    } else {
+                        learnt.push(q);
+                    }
+                }
+            }
+            // the latest literal on the trail that's in the clause so far
+            loop {
+                index -= 1;
+                if seen[self.trail[index].var()] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            seen[lit.var()] = false;
+            at_level -= 1;
+            if at_level == 0 {
+                learnt[0] = !lit;
+                break;
+            }
+            clause = self.reasons[lit.var()].unwrap();
+            skip_first = true;
+        }
+
+        let mut back_to = 0;
+        if learnt.len() > 1 {
+            let highest = (1..learnt.len())
+                .max_by_key(|&k| self.levels[learnt[k].var()])
+                .unwrap();
+            learnt.swap(1, highest);
+            back_to = self.levels[learnt[1].var()];
+        }
+        (learnt, back_to)
+    }
+
+    fn bump_activity(&mut self, var: usize) {
+        self.activity[var] += self.bump;
+        if self.activity[var] > 1e100 {
+            for a in &mut self.activity {
+                *a *= 1e-100;
+            }
+            self.bump *= 1e-100;
+        }
+    }
+
+    // the unset variable with the most activity
+    fn pick(&self) -> Option<usize> {
+        (0..self.vars())
+            .filter(|&v| self.values[v].is_none())
+            .max_by(|&a, &b| self.activity[a].total_cmp(&self.activity[b]))
+    }
+}
+
+// 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, ... for i from 1
+fn luby(mut i: u64) -> u64 {
+    loop {
+        // the smallest 2^k - 1 at or above i
+        let mut size = 1;
+        while size < i {
+            size = 2 * size + 1;
+        }
+        if size == i {
+            return size.div_ceil(2);
+        }
+        i -= size / 2;
+    }
+}
+
+#[test]
+fn test() {
+    use crate::rng::Rng;
+
+    assert_eq!(
+        vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, 1],
+        (1..=16).map(luby).collect::<Vec<_>>()
+    );
+
+    let mut s = Solver::new();
+    let [a, b, c] = [s.new_var(), s.new_var(), s.new_var()];
+    assert!(s.add_clause(&[a, b]));
+    assert!(s.add_clause(&[!a, c]));
+    assert!(s.add_clause(&[!b, c]));
+    assert!(s.add_clause(&[a, !a])); // always true
+    let model = s.solve().unwrap();
+    assert!(model[c.var()] && (model[a.var()] || model[b.var()]));
+    // found by propagation alone
+    assert!(!s.add_clause(&[!c]));
+    assert_eq!(None, s.solve());
+
+    // no way to put 5 pigeons in 4 holes, one to a hole
+    let mut s = Solver::new();
+    let (pigeons, holes) = (5, 4);
+    let in_hole: Vec<Vec<Lit>> = (0..pigeons)
+        .map(|_| (0..holes).map(|_| s.new_var()).collect())
+        .collect();
+    for p in &in_hole {
+        s.add_clause(p);
+    }
+    for p in 0..pigeons {
+        for q in p + 1..pigeons {
+            for (&a, &b) in in_hole[p].iter().zip(&in_hole[q]) {
+                s.add_clause(&[!a, !b]);
+            }
+        }
+    }
+    assert_eq!(None, s.solve());
+
+    // random 3-SAT below the threshold, where almost every instance can be satisfied; any
+    // model found must satisfy every clause, and ruling each one out finds the others
+    let mut rng = Rng::new(12345);
+    let mut next = |m: usize| rng.below(m);
+    let mut solved = 0;
+    for _ in 0..20 {
+        let vars = 60;
+        let mut s = Solver::new();
+        let lits: Vec<Lit> = (0..vars).map(|_| s.new_var()).collect();
+        let clauses: Vec<Vec<Lit>> = (0..200)
+            .map(|_| {
+                (0..3)
+                    .map(|_| {
+                        let l = lits[next(vars)];
+                        if next(2) == 0 {
+                            l
+                        } else {
+                            !l
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        for clause in &clauses {
+            s.add_clause(clause);
+        }
+        let mut models = vec![];
+        while let Some(model) = s.solve() {
+            assert!(clauses
+                .iter()
+                .all(|c| c.iter().any(|l| model[l.var()] == l.is_positive())));
+            let blocking: Vec<Lit> = lits
+                .iter()
+                .map(|&l| Lit::new(l.var(), !model[l.var()]))
+                .collect();
+            models.push(model);
+            if models.len() == 5 {
+                break;
+            }
+            s.add_clause(&blocking);
+        }
+        models.sort();
+        models.dedup();
+        solved += !models.is_empty() as usize;
+        assert!(models.len() == 5 || s.solve().is_none());
+    }
+    assert!(solved >= 15);
+}
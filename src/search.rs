@@ -0,0 +1,352 @@
+// Generic searches over an implicit graph of states.
+//
+// A puzzle describes its graph by implementing SearchProblem; the search functions return the
+// full path taken (each move and the state it led to) so answers can be explained, not just
+// costed.  Every search remembers the states it has already reached so equivalent states
+// arrived at via different routes are only expanded once.
+#![allow(dead_code)]
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+pub trait SearchProblem {
+    type State: Clone + Eq + Hash;
+    type Move: Clone;
+
+    fn start(&self) -> Self::State;
+
+    fn is_goal(&self, state: &Self::State) -> bool;
+
+    // every (move, resulting state, cost of move) reachable from state
+    fn successors(&self, state: &Self::State) -> Vec<(Self::Move, Self::State, u32)>;
+
+    // estimated remaining cost to a goal.  astar only guarantees the cheapest path if this never
+    // overestimates.
+    fn heuristic(&self, _state: &Self::State) -> u32 {
+        0
+    }
+}
+
+#[derive(Debug)]
+pub struct Path<S, M> {
+    pub start: S,
+    pub steps: Vec<(M, S)>,
+    pub cost: u32,
+}
+
+impl<S, M> Path<S, M> {
+    pub fn moves(&self) -> impl Iterator<Item = &M> {
+        self.steps.iter().map(|(m, _)| m)
+    }
+
+    pub fn goal(&self) -> &S {
+        self.steps.last().map(|(_, s)| s).unwrap_or(&self.start)
+    }
+}
+
+// Reached states are kept in an arena so paths can be rebuilt from parent links.
+struct Node<S, M> {
+    state: S,
+    parent: Option<(usize, M)>,
+    cost: u32,
+}
+
+fn reconstruct<S: Clone, M: Clone>(nodes: &[Node<S, M>], mut i: usize) -> Path<S, M> {
+    let cost = nodes[i].cost;
+    let mut steps = vec![];
+    while let Some((parent, m)) = &nodes[i].parent {
+        steps.push((m.clone(), nodes[i].state.clone()));
+        i = *parent;
+    }
+    steps.reverse();
+    Path {
+        start: nodes[i].state.clone(),
+        steps,
+        cost,
+    }
+}
+
+#[derive(Eq, PartialEq)]
+struct Frontier {
+    priority: u32,
+    cost: u32,
+    node: usize,
+}
+
+// BinaryHeap is a max-heap, so order lowest priority first.  Ties prefer the deeper (more
+// expensive so far) node, which is closer to a goal if the heuristic is any good.
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .cmp(&self.priority)
+            .then(self.cost.cmp(&other.cost))
+            .then(other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn best_first<P: SearchProblem>(
+    problem: &P,
+    use_heuristic: bool,
+) -> Option<Path<P::State, P::Move>> {
+    let start = problem.start();
+    let mut nodes = vec![Node {
+        state: start.clone(),
+        parent: None,
+        cost: 0,
+    }];
+    let mut best: HashMap<P::State, usize> = HashMap::new();
+    best.insert(start, 0);
+    let mut frontier = BinaryHeap::new();
+    frontier.push(Frontier {
+        priority: 0,
+        cost: 0,
+        node: 0,
+    });
+
+    while let Some(Frontier { cost, node, .. }) = frontier.pop() {
+        if cost > nodes[node].cost {
+            // a cheaper route to this state was found after this entry was queued
+            continue;
+        }
+        if problem.is_goal(&nodes[node].state) {
+            return Some(reconstruct(&nodes, node));
+        }
+        for (m, next, step_cost) in problem.successors(&nodes[node].state) {
+            let next_cost = cost + step_cost;
+            let i = match best.get(&next) {
+                Some(&i) if nodes[i].cost <= next_cost => continue,
+                Some(&i) => {
+                    nodes[i].cost = next_cost;
+                    nodes[i].parent = Some((node, m));
+                    i
+                }
+                None => {
+                    nodes.push(Node {
+                        state: next.clone(),
+                        parent: Some((node, m)),
+                        cost: next_cost,
+                    });
+                    best.insert(next, nodes.len() - 1);
+                    nodes.len() - 1
+                }
+            };
+            let h = if use_heuristic {
+                problem.heuristic(&nodes[i].state)
+            } else {
+                0
+            };
+            frontier.push(Frontier {
+                priority: next_cost + h,
+                cost: next_cost,
+                node: i,
+            });
+        }
+    }
+    None
+}
+
+// Cheapest path to a goal.
+pub fn dijkstra<P: SearchProblem>(problem: &P) -> Option<Path<P::State, P::Move>> {
+    best_first(problem, false)
+}
+
+// Cheapest path to a goal, guided by the problem's heuristic.
+pub fn astar<P: SearchProblem>(problem: &P) -> Option<Path<P::State, P::Move>> {
+    best_first(problem, true)
+}
+
+// Path to a goal with the fewest moves, ignoring move costs (the returned path is still costed).
+pub fn bfs<P: SearchProblem>(problem: &P) -> Option<Path<P::State, P::Move>> {
+    let start = problem.start();
+    let mut nodes = vec![Node {
+        state: start.clone(),
+        parent: None,
+        cost: 0,
+    }];
+    let mut seen: HashMap<P::State, usize> = HashMap::new();
+    seen.insert(start, 0);
+    let mut queue = VecDeque::from([0]);
+
+    while let Some(node) = queue.pop_front() {
+        if problem.is_goal(&nodes[node].state) {
+            return Some(reconstruct(&nodes, node));
+        }
+        for (m, next, step_cost) in problem.successors(&nodes[node].state) {
+            if seen.contains_key(&next) {
+                continue;
+            }
+            nodes.push(Node {
+                state: next.clone(),
+                parent: Some((node, m)),
+                cost: nodes[node].cost + step_cost,
+            });
+            seen.insert(next, nodes.len() - 1);
+            queue.push_back(nodes.len() - 1);
+        }
+    }
+    None
+}
+
+// Depth-first search, trying successors in the order given.  Finds a path quickly when the
+// problem's successors are ordered well, but with no guarantee it's the cheapest or shortest.
+pub fn dfs<P: SearchProblem>(problem: &P) -> Option<Path<P::State, P::Move>> {
+    let mut nodes = vec![Node {
+        state: problem.start(),
+        parent: None,
+        cost: 0,
+    }];
+    let mut expanded = HashSet::new();
+    let mut stack = vec![0];
+
+    while let Some(node) = stack.pop() {
+        if !expanded.insert(nodes[node].state.clone()) {
+            continue;
+        }
+        if problem.is_goal(&nodes[node].state) {
+            return Some(reconstruct(&nodes, node));
+        }
+        let first_child = nodes.len();
+        for (m, next, step_cost) in problem.successors(&nodes[node].state) {
+            if expanded.contains(&next) {
+                continue;
+            }
+            nodes.push(Node {
+                state: next,
+                parent: Some((node, m)),
+                cost: nodes[node].cost + step_cost,
+            });
+        }
+        // push in reverse so the first successor is explored first
+        stack.extend((first_child..nodes.len()).rev());
+    }
+    None
+}
+
+// Iterative deepening depth-first search: the same answer as bfs (fewest moves) but only keeps
+// one path in memory at a time, plus the shallowest depth each state was reached at during the
+// current iteration.  Gives up after max_depth moves.
+pub fn iddfs<P: SearchProblem>(problem: &P, max_depth: usize) -> Option<Path<P::State, P::Move>> {
+    let start = problem.start();
+    for limit in 0..=max_depth {
+        let mut steps = vec![];
+        let mut seen = HashMap::new();
+        if depth_limited(problem, &start, limit, &mut steps, &mut seen) {
+            let cost = steps.iter().map(|(_, _, c)| c).sum();
+            return Some(Path {
+                start,
+                steps: steps.into_iter().map(|(m, s, _)| (m, s)).collect(),
+                cost,
+            });
+        }
+    }
+    None
+}
+
+type Steps<P> = Vec<(<P as SearchProblem>::Move, <P as SearchProblem>::State, u32)>;
+
+fn depth_limited<P: SearchProblem>(
+    problem: &P,
+    state: &P::State,
+    remaining: usize,
+    steps: &mut Steps<P>,
+    seen: &mut HashMap<P::State, usize>,
+) -> bool {
+    if problem.is_goal(state) {
+        return true;
+    }
+    if remaining == 0 {
+        return false;
+    }
+    for (m, next, step_cost) in problem.successors(state) {
+        // skip states already explored with at least as many moves left
+        match seen.get(&next) {
+            Some(&r) if r >= remaining - 1 => continue,
+            _ => {
+                seen.insert(next.clone(), remaining - 1);
+            }
+        }
+        steps.push((m, next.clone(), step_cost));
+        if depth_limited(problem, &next, remaining - 1, steps, seen) {
+            return true;
+        }
+        steps.pop();
+    }
+    false
+}
+
+// Small weighted graph used to check every search agrees:
+//
+//   0 --1-- 1 --1-- 2 --1-- 5
+//   |                       |
+//   +------------5----------+
+//   3 (dead end from 0, cost 1)
+#[cfg(test)]
+struct TestGraph;
+
+#[cfg(test)]
+impl SearchProblem for TestGraph {
+    type State = u32;
+    type Move = char;
+
+    fn start(&self) -> u32 {
+        0
+    }
+
+    fn is_goal(&self, state: &u32) -> bool {
+        *state == 5
+    }
+
+    fn successors(&self, state: &u32) -> Vec<(char, u32, u32)> {
+        match state {
+            0 => vec![('a', 1, 1), ('b', 5, 5), ('c', 3, 1)],
+            1 => vec![('d', 2, 1), ('e', 0, 1)],
+            2 => vec![('f', 5, 1), ('g', 1, 1)],
+            _ => vec![],
+        }
+    }
+
+    fn heuristic(&self, state: &u32) -> u32 {
+        match state {
+            1 => 2,
+            2 => 1,
+            _ => 0,
+        }
+    }
+}
+
+#[test]
+fn test() {
+    let path = dijkstra(&TestGraph).unwrap();
+    assert_eq!(3, path.cost);
+    assert_eq!("adf", path.moves().collect::<String>());
+    assert_eq!(5, *path.goal());
+
+    let path = astar(&TestGraph).unwrap();
+    assert_eq!(3, path.cost);
+    assert_eq!("adf", path.moves().collect::<String>());
+
+    // fewest moves is the expensive direct edge
+    let path = bfs(&TestGraph).unwrap();
+    assert_eq!(5, path.cost);
+    assert_eq!("b", path.moves().collect::<String>());
+
+    // first route found, trying moves in order
+    let path = dfs(&TestGraph).unwrap();
+    assert_eq!(3, path.cost);
+    assert_eq!("adf", path.moves().collect::<String>());
+
+    let path = iddfs(&TestGraph, 10).unwrap();
+    assert_eq!(5, path.cost);
+    assert_eq!("b", path.moves().collect::<String>());
+    assert_eq!(vec![('b', 5)], path.steps);
+
+    assert!(iddfs(&TestGraph, 0).is_none());
+}